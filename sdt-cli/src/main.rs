@@ -0,0 +1,170 @@
+use std::{
+    fs,
+    io::{self, Read},
+    process::ExitCode,
+};
+
+use clap::{error::ErrorKind, CommandFactory, Parser, Subcommand};
+use sdt::{
+    error::SdtError,
+    node::SdtClaim,
+    service::{SdtInput, SdtResult, SdtService},
+    Sdt,
+};
+use serde_json::json;
+
+/// Exit code of failed commands and failed verifications
+const EXIT_FAILURE: u8 = 1;
+/// Exit code of unreadable inputs
+const EXIT_INPUT_ERROR: u8 = 2;
+
+/// Issue, mutate, select and verify selective disclosure tries.
+///
+/// One `<FILE>` argument per command can be `-` to read from stdin, results are written to stdout as JSON.
+#[derive(Parser)]
+#[command(name = "sdt", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Creates a trie from inception claims
+    Incept {
+        #[arg(long)]
+        subject: String,
+        #[arg(long, value_name = "FILE", default_value = "-")]
+        claim: String,
+    },
+    /// Appends a mutation to a trie
+    Mutate {
+        #[arg(long, value_name = "FILE")]
+        sdt: String,
+        #[arg(long, value_name = "FILE")]
+        claim: String,
+    },
+    /// Keeps only the claims of a query, the rest is replaced by proofs
+    Select {
+        #[arg(long, value_name = "FILE")]
+        sdt: String,
+        #[arg(long, value_name = "FILE")]
+        query: String,
+    },
+    /// Generates the root proof of a trie
+    Proof {
+        #[arg(long, value_name = "FILE", default_value = "-")]
+        sdt: String,
+    },
+    /// Verifies a trie against a root proof, exits with 1 when it doesn't match
+    Verify {
+        #[arg(long, value_name = "FILE")]
+        sdt: String,
+        #[arg(long)]
+        proof: String,
+    },
+    /// Shows the subject, root proof and current claims of a trie
+    Show {
+        #[arg(long, value_name = "FILE", default_value = "-")]
+        sdt: String,
+    },
+}
+
+impl Command {
+    fn files(&self) -> Vec<&str> {
+        match self {
+            Command::Incept { claim, .. } => vec![claim],
+            Command::Mutate { sdt, claim } => vec![sdt, claim],
+            Command::Select { sdt, query } => vec![sdt, query],
+            Command::Proof { sdt } | Command::Verify { sdt, .. } | Command::Show { sdt } => {
+                vec![sdt]
+            }
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    // stdin can only be read once
+    if cli.command.files().iter().filter(|f| **f == "-").count() > 1 {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "Only one file argument can be `-`",
+            )
+            .exit();
+    }
+    match run(cli.command) {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::from(EXIT_INPUT_ERROR)
+        }
+    }
+}
+
+fn run(command: Command) -> Result<ExitCode, SdtError> {
+    let input = match command {
+        Command::Incept { subject, claim } => SdtInput::Inception {
+            subject,
+            claim: read_claim(&claim)?,
+            store: false,
+        },
+        Command::Mutate { sdt, claim } => SdtInput::Mutation {
+            sdt: read_sdt(&sdt)?.into(),
+            claim: read_claim(&claim)?,
+        },
+        Command::Select { sdt, query } => SdtInput::Selection {
+            sdt: read_sdt(&sdt)?.into(),
+            query: read_input(&query)?,
+        },
+        Command::Proof { sdt } => SdtInput::Proof(read_sdt(&sdt)?.into()),
+        Command::Verify { sdt, proof } => SdtInput::Verification {
+            sdt: read_sdt(&sdt)?.into(),
+            proof,
+        },
+        Command::Show { sdt } => return show(&read_sdt(&sdt)?),
+    };
+    execute(&input)
+}
+
+fn execute(input: &SdtInput) -> Result<ExitCode, SdtError> {
+    let output = SdtService(serde_json::to_string(input)?).execute();
+    println!("{output}");
+    let code = match serde_json::from_str::<SdtResult>(&output) {
+        Ok(SdtResult::Error { .. }) | Ok(SdtResult::Verification { valid: false }) | Err(_) => {
+            ExitCode::from(EXIT_FAILURE)
+        }
+        Ok(_) => ExitCode::SUCCESS,
+    };
+    Ok(code)
+}
+
+fn show(sdt: &Sdt) -> Result<ExitCode, SdtError> {
+    let summary = json!({
+        "subject": sdt.subject,
+        "version": sdt.version,
+        "items": sdt.items().len(),
+        "proof": sdt.gen_proof()?,
+        "claims": sdt.current_node().to_claim(),
+    });
+    println!("{}", serde_json::to_string_pretty(&summary)?);
+    Ok(ExitCode::SUCCESS)
+}
+
+fn read_input(path: &str) -> Result<String, SdtError> {
+    if path == "-" {
+        let mut input = String::new();
+        io::stdin().read_to_string(&mut input)?;
+        return Ok(input);
+    }
+    Ok(fs::read_to_string(path)?)
+}
+
+fn read_claim(path: &str) -> Result<SdtClaim, SdtError> {
+    Ok(serde_json::from_str(&read_input(path)?)?)
+}
+
+fn read_sdt(path: &str) -> Result<Sdt, SdtError> {
+    Ok(serde_json::from_str(&read_input(path)?)?)
+}
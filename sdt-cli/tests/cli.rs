@@ -0,0 +1,118 @@
+use std::{
+    fs,
+    io::Write,
+    path::Path,
+    process::{Command, Output, Stdio},
+};
+
+use serde_json::Value;
+
+fn sdt(args: &[&str], stdin: Option<&str>) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_sdt"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.unwrap_or_default().as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn write(dir: &Path, name: &str, content: &str) -> String {
+    let path = dir.join(name);
+    fs::write(&path, content).unwrap();
+    path.to_str().unwrap().to_owned()
+}
+
+fn stdout_json(output: &Output) -> Value {
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[test]
+fn issue_select_verify_test() {
+    let dir = tempfile::tempdir().unwrap();
+    let claim = r#"{"personal": {"name": "Adem", "surname": "Çağlın", "age_over_18": false}}"#;
+    let incepted = sdt(&["incept", "--subject", "did:p2p:123456"], Some(claim));
+    assert!(incepted.status.success());
+    assert_eq!(stdout_json(&incepted)["kind"], "Inception");
+    let sdt_file = write(
+        dir.path(),
+        "sdt.json",
+        &String::from_utf8_lossy(&incepted.stdout),
+    );
+
+    let mutation = write(
+        dir.path(),
+        "mutation.json",
+        r#"{"personal": {"age_over_18": true}}"#,
+    );
+    let mutated = sdt(&["mutate", "--sdt", &sdt_file, "--claim", &mutation], None);
+    assert!(mutated.status.success());
+    let sdt_file = write(
+        dir.path(),
+        "sdt.json",
+        &String::from_utf8_lossy(&mutated.stdout),
+    );
+
+    let proof = sdt(&["proof", "--sdt", &sdt_file], None);
+    assert!(proof.status.success());
+    let proof = stdout_json(&proof)["proof"].as_str().unwrap().to_owned();
+
+    let query = "{\n personal {\n age_over_18\n }\n}";
+    let selected = sdt(&["select", "--sdt", &sdt_file, "--query", "-"], Some(query));
+    assert!(selected.status.success());
+    let selected_file = write(
+        dir.path(),
+        "selected.json",
+        &String::from_utf8_lossy(&selected.stdout),
+    );
+
+    let verified = sdt(
+        &["verify", "--sdt", &selected_file, "--proof", &proof],
+        None,
+    );
+    assert!(verified.status.success());
+    assert_eq!(stdout_json(&verified)["valid"], true);
+
+    let shown = sdt(&["show", "--sdt", &selected_file], None);
+    assert!(shown.status.success());
+    let shown = stdout_json(&shown);
+    assert_eq!(shown["items"], 2);
+    assert_eq!(shown["proof"], proof.as_str());
+    assert_eq!(shown["claims"]["personal"]["age_over_18"], true);
+    assert!(shown["claims"]["personal"].get("name").is_none());
+}
+
+#[test]
+fn verify_failure_test() {
+    let dir = tempfile::tempdir().unwrap();
+    let incepted = sdt(
+        &["incept", "--subject", "did:p2p:123456"],
+        Some(r#"{"name": "Adem"}"#),
+    );
+    let sdt_file = write(
+        dir.path(),
+        "sdt.json",
+        &String::from_utf8_lossy(&incepted.stdout),
+    );
+    let verified = sdt(&["verify", "--sdt", &sdt_file, "--proof", "0x1234"], None);
+    assert_eq!(verified.status.code(), Some(1));
+    assert_eq!(stdout_json(&verified)["kind"], "Error");
+}
+
+#[test]
+fn invalid_input_test() {
+    let output = sdt(&["proof"], Some("not json"));
+    assert_eq!(output.status.code(), Some(2));
+    let output = sdt(&["show", "--sdt", "/nonexistent/sdt.json"], None);
+    assert_eq!(output.status.code(), Some(2));
+    let output = sdt(&["mutate", "--sdt", "-", "--claim", "-"], Some("{}"));
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Only one file argument"));
+}
@@ -0,0 +1,16 @@
+use std::{env, path::PathBuf};
+
+/// Generates `sdt.h` into `OUT_DIR`. The checked-in `include/sdt.h` is only rewritten when
+/// `SDT_FFI_UPDATE_HEADER` is set, `tests/c_program.rs` fails when it is outdated.
+fn main() {
+    let crate_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-env-changed=SDT_FFI_UPDATE_HEADER");
+    let bindings = cbindgen::generate(&crate_dir).expect("Unable to generate the C header");
+    bindings.write_to_file(out_dir.join("sdt.h"));
+    if env::var_os("SDT_FFI_UPDATE_HEADER").is_some() {
+        bindings.write_to_file(crate_dir.join("include/sdt.h"));
+    }
+}
@@ -0,0 +1,125 @@
+/*
+ * Selective disclosure tries, see src/lib.rs for the ownership rules:
+ * input strings are borrowed, strings written to `out` are owned by the caller
+ * and must be released with `sdt_string_free`.
+ */
+
+#ifndef SDT_H
+#define SDT_H
+
+/* Generated by cbindgen from src/lib.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Result code of every function, `SDT_STATUS_OK` is zero
+typedef enum SdtStatus {
+  SDT_STATUS_OK = 0,
+  // A required pointer is null
+  SDT_STATUS_NULL_POINTER = 1,
+  // An input string is not valid UTF-8
+  SDT_STATUS_INVALID_UTF8 = 2,
+  SDT_STATUS_IO_ERROR = 3,
+  SDT_STATUS_JSON_ERROR = 4,
+  SDT_STATUS_HEX_ERROR = 5,
+  SDT_STATUS_CBOR_ERROR = 6,
+  // The trie doesn't match the proof
+  SDT_STATUS_VERIFICATION_ERROR = 7,
+  SDT_STATUS_UNSUPPORTED_VERSION = 8,
+  SDT_STATUS_INVALID_KEY = 9,
+  SDT_STATUS_INVALID_SIGNATURE = 10,
+  SDT_STATUS_SD_JWT_ERROR = 11,
+  SDT_STATUS_CREDENTIAL_ERROR = 12,
+  SDT_STATUS_COMPACT_ERROR = 13,
+  SDT_STATUS_OTHER = 14,
+  // The library panicked, the panic didn't unwind into the caller
+  SDT_STATUS_PANIC = 15,
+  SDT_STATUS_QUERY_ERROR = 16,
+  SDT_STATUS_UNKNOWN_HANDLE = 17,
+  SDT_STATUS_SESSION_FULL = 18,
+  SDT_STATUS_SESSION_REQUIRED = 19,
+  SDT_STATUS_PIPELINE_ERROR = 20,
+  SDT_STATUS_UNKNOWN_SUBJECT = 21,
+  // The stored chain moved on since the head the write was based on
+  SDT_STATUS_STORE_CONFLICT = 22,
+  SDT_STATUS_ANCHOR_ERROR = 23,
+  SDT_STATUS_POSITION_ERROR = 24,
+  SDT_STATUS_FORK_ERROR = 25,
+  SDT_STATUS_DID_ERROR = 26,
+  // A mutation isn't signed by the keys committed before it
+  SDT_STATUS_AUTHORIZATION_ERROR = 27,
+  SDT_STATUS_WITNESS_ERROR = 28,
+  SDT_STATUS_STATUS_ERROR = 29,
+  // The status list marks the trie as revoked or suspended
+  SDT_STATUS_REVOKED = 30,
+  // The store holds a file it can't read back
+  SDT_STATUS_STORE_ERROR = 31,
+} SdtStatus;
+
+// Message of the last failed call on this thread, null when the last call succeeded.
+//
+// The string is owned by the library and valid until the next call on the same thread.
+const char *sdt_last_error(void);
+
+// Releases a string returned by the library, null is ignored.
+//
+// # Safety
+//
+// `s` must be null or a string written to an `out` pointer by this library, and
+// must not be used after this call.
+void sdt_string_free(char *s);
+
+// Creates a trie from the inception claims and writes it as JSON.
+//
+// # Safety
+//
+// `subject` and `claim` must be null or valid NUL terminated strings, `out` must be
+// null or valid for writes.
+enum SdtStatus sdt_incept(const char *subject, const char *claim, char **out);
+
+// Appends a mutation of the claims to a trie and writes the new trie as JSON.
+//
+// # Safety
+//
+// `sdt` and `claim` must be null or valid NUL terminated strings, `out` must be null
+// or valid for writes.
+enum SdtStatus sdt_mutate(const char *sdt, const char *claim, char **out);
+
+// Keeps only the claims of the query, the rest is replaced by proofs.
+//
+// # Safety
+//
+// `sdt` and `query` must be null or valid NUL terminated strings, `out` must be null
+// or valid for writes.
+enum SdtStatus sdt_select(const char *sdt, const char *query, char **out);
+
+// Writes the root proof of a trie, e.g. `0x5ddd...`.
+//
+// # Safety
+//
+// `sdt` must be null or a valid NUL terminated string, `out` must be null or valid
+// for writes.
+enum SdtStatus sdt_gen_proof(const char *sdt, char **out);
+
+// Returns `SDT_STATUS_OK` when the trie matches the proof and
+// `SDT_STATUS_VERIFICATION_ERROR` when it doesn't.
+//
+// # Safety
+//
+// `sdt` and `proof` must be null or valid NUL terminated strings.
+enum SdtStatus sdt_verify(const char *sdt, const char *proof);
+
+// Executes a serialized `SdtInput` and writes the serialized `SdtResult`.
+//
+// Failed commands are reported in the result, the status only reflects the call itself.
+//
+// # Safety
+//
+// `input` must be null or a valid NUL terminated string, `out` must be null or valid
+// for writes.
+enum SdtStatus sdt_dispatch(const char *input, char **out);
+
+#endif  /* SDT_H */
@@ -0,0 +1,331 @@
+//! C ABI of selective disclosure tries.
+//!
+//! Tries, claims and nodes cross the boundary as NUL terminated UTF-8 JSON strings.
+//! Input strings are borrowed and never freed by the library. Strings written to an
+//! `out` pointer are owned by the caller and must be released with `sdt_string_free`.
+//! Every function returns an `SdtStatus`, on failure `out` is left untouched and
+//! `sdt_last_error` describes the error.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+use sdt::{error::SdtError, node::SdtClaim, service::SdtService, Sdt};
+
+/// Result code of every function, `SDT_STATUS_OK` is zero
+#[repr(C)]
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum SdtStatus {
+    Ok = 0,
+    /// A required pointer is null
+    NullPointer = 1,
+    /// An input string is not valid UTF-8
+    InvalidUtf8 = 2,
+    IoError = 3,
+    JsonError = 4,
+    HexError = 5,
+    CborError = 6,
+    /// The trie doesn't match the proof
+    VerificationError = 7,
+    UnsupportedVersion = 8,
+    InvalidKey = 9,
+    InvalidSignature = 10,
+    SdJwtError = 11,
+    CredentialError = 12,
+    CompactError = 13,
+    Other = 14,
+    /// The library panicked, the panic didn't unwind into the caller
+    Panic = 15,
+    QueryError = 16,
+    UnknownHandle = 17,
+    SessionFull = 18,
+    SessionRequired = 19,
+    PipelineError = 20,
+    UnknownSubject = 21,
+    /// The stored chain moved on since the head the write was based on
+    StoreConflict = 22,
+    AnchorError = 23,
+    PositionError = 24,
+    ForkError = 25,
+    DidError = 26,
+    /// A mutation isn't signed by the keys committed before it
+    AuthorizationError = 27,
+    WitnessError = 28,
+    StatusError = 29,
+    /// The status list marks the trie as revoked or suspended
+    Revoked = 30,
+    /// The store holds a file it can't read back
+    StoreError = 31,
+}
+
+impl From<&SdtError> for SdtStatus {
+    fn from(err: &SdtError) -> Self {
+        match err {
+            SdtError::StdError(_) => SdtStatus::IoError,
+            SdtError::SerdeJsonError(_) => SdtStatus::JsonError,
+            SdtError::HexError(_) => SdtStatus::HexError,
+            SdtError::CborError(_) => SdtStatus::CborError,
+            SdtError::VerificationError { .. } => SdtStatus::VerificationError,
+            SdtError::UnsupportedVersion(_) => SdtStatus::UnsupportedVersion,
+            SdtError::InvalidKey(_) => SdtStatus::InvalidKey,
+            SdtError::InvalidSignature => SdtStatus::InvalidSignature,
+            SdtError::SdJwtError(_) => SdtStatus::SdJwtError,
+            SdtError::CredentialError(_) => SdtStatus::CredentialError,
+            SdtError::CompactError(_) => SdtStatus::CompactError,
+            SdtError::QueryError { .. } => SdtStatus::QueryError,
+            SdtError::UnknownHandle(_) => SdtStatus::UnknownHandle,
+            SdtError::SessionFull(_) => SdtStatus::SessionFull,
+            SdtError::SessionRequired => SdtStatus::SessionRequired,
+            SdtError::PipelineError(_) => SdtStatus::PipelineError,
+            SdtError::UnknownSubject(_) => SdtStatus::UnknownSubject,
+            SdtError::StoreConflict { .. } => SdtStatus::StoreConflict,
+            SdtError::StoreError(_) => SdtStatus::StoreError,
+            SdtError::AnchorError(_) => SdtStatus::AnchorError,
+            SdtError::PositionError { .. } => SdtStatus::PositionError,
+            SdtError::ForkError(_) => SdtStatus::ForkError,
+            SdtError::DidError(_) => SdtStatus::DidError,
+            SdtError::AuthorizationError { .. } => SdtStatus::AuthorizationError,
+            SdtError::WitnessError(_) => SdtStatus::WitnessError,
+            SdtError::StatusError(_) => SdtStatus::StatusError,
+            SdtError::Revoked { .. } => SdtStatus::Revoked,
+            SdtError::Other(_) => SdtStatus::Other,
+        }
+    }
+}
+
+struct FfiError(SdtStatus, String);
+
+impl From<SdtError> for FfiError {
+    fn from(err: SdtError) -> Self {
+        FfiError(SdtStatus::from(&err), err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for FfiError {
+    fn from(err: serde_json::Error) -> Self {
+        FfiError::from(SdtError::from(err))
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Message of the last failed call on this thread, null when the last call succeeded.
+///
+/// The string is owned by the library and valid until the next call on the same thread.
+#[no_mangle]
+pub extern "C" fn sdt_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |msg| msg.as_ptr())
+    })
+}
+
+/// Releases a string returned by the library, null is ignored.
+///
+/// # Safety
+///
+/// `s` must be null or a string written to an `out` pointer by this library, and
+/// must not be used after this call.
+#[no_mangle]
+pub unsafe extern "C" fn sdt_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Creates a trie from the inception claims and writes it as JSON.
+///
+/// # Safety
+///
+/// `subject` and `claim` must be null or valid NUL terminated strings, `out` must be
+/// null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sdt_incept(
+    subject: *const c_char,
+    claim: *const c_char,
+    out: *mut *mut c_char,
+) -> SdtStatus {
+    run(|| {
+        let subject = read_str(subject)?;
+        let claim: SdtClaim = serde_json::from_str(read_str(claim)?)?;
+        let sdt = Sdt::new(subject, claim.to_node());
+        write_out(out, serde_json::to_string(&sdt)?)
+    })
+}
+
+/// Appends a mutation of the claims to a trie and writes the new trie as JSON.
+///
+/// # Safety
+///
+/// `sdt` and `claim` must be null or valid NUL terminated strings, `out` must be null
+/// or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sdt_mutate(
+    sdt: *const c_char,
+    claim: *const c_char,
+    out: *mut *mut c_char,
+) -> SdtStatus {
+    run(|| {
+        let mut sdt: Sdt = serde_json::from_str(read_str(sdt)?)?;
+        let claim: SdtClaim = serde_json::from_str(read_str(claim)?)?;
+        let mutated = sdt.mutate(claim.to_node()).build();
+        write_out(out, serde_json::to_string(&mutated)?)
+    })
+}
+
+/// Keeps only the claims of the query, the rest is replaced by proofs.
+///
+/// # Safety
+///
+/// `sdt` and `query` must be null or valid NUL terminated strings, `out` must be null
+/// or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sdt_select(
+    sdt: *const c_char,
+    query: *const c_char,
+    out: *mut *mut c_char,
+) -> SdtStatus {
+    run(|| {
+        let sdt: Sdt = serde_json::from_str(read_str(sdt)?)?;
+        let selected = sdt.select(read_str(query)?)?;
+        write_out(out, serde_json::to_string(&selected)?)
+    })
+}
+
+/// Writes the root proof of a trie, e.g. `0x5ddd...`.
+///
+/// # Safety
+///
+/// `sdt` must be null or a valid NUL terminated string, `out` must be null or valid
+/// for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sdt_gen_proof(sdt: *const c_char, out: *mut *mut c_char) -> SdtStatus {
+    run(|| {
+        let sdt: Sdt = serde_json::from_str(read_str(sdt)?)?;
+        write_out(out, sdt.gen_proof()?)
+    })
+}
+
+/// Returns `SDT_STATUS_OK` when the trie matches the proof and
+/// `SDT_STATUS_VERIFICATION_ERROR` when it doesn't.
+///
+/// # Safety
+///
+/// `sdt` and `proof` must be null or valid NUL terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn sdt_verify(sdt: *const c_char, proof: *const c_char) -> SdtStatus {
+    run(|| {
+        let sdt: Sdt = serde_json::from_str(read_str(sdt)?)?;
+        sdt.verify(read_str(proof)?)?;
+        Ok(())
+    })
+}
+
+/// Executes a serialized `SdtInput` and writes the serialized `SdtResult`.
+///
+/// Failed commands are reported in the result, the status only reflects the call itself.
+///
+/// # Safety
+///
+/// `input` must be null or a valid NUL terminated string, `out` must be null or valid
+/// for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sdt_dispatch(input: *const c_char, out: *mut *mut c_char) -> SdtStatus {
+    run(|| {
+        let input = read_str(input)?;
+        write_out(out, SdtService(input.to_owned()).execute())
+    })
+}
+
+fn run<F: FnOnce() -> Result<(), FfiError>>(f: F) -> SdtStatus {
+    let result = panic::catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|_| Err(FfiError(SdtStatus::Panic, "Unexpected panic".to_owned())));
+    let (status, msg) = match result {
+        Ok(()) => (SdtStatus::Ok, None),
+        Err(FfiError(status, msg)) => (status, CString::new(msg).ok()),
+    };
+    LAST_ERROR.with(|last| *last.borrow_mut() = msg);
+    status
+}
+
+unsafe fn read_str<'a>(s: *const c_char) -> Result<&'a str, FfiError> {
+    if s.is_null() {
+        return Err(FfiError(SdtStatus::NullPointer, "Null input".to_owned()));
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|e| FfiError(SdtStatus::InvalidUtf8, e.to_string()))
+}
+
+unsafe fn write_out(out: *mut *mut c_char, value: String) -> Result<(), FfiError> {
+    if out.is_null() {
+        return Err(FfiError(SdtStatus::NullPointer, "Null output".to_owned()));
+    }
+    let value = CString::new(value).map_err(|e| FfiError(SdtStatus::Other, e.to_string()))?;
+    *out = value.into_raw();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn take(s: *mut c_char) -> String {
+        let value = unsafe { CStr::from_ptr(s) }.to_str().unwrap().to_owned();
+        unsafe { sdt_string_free(s) };
+        value
+    }
+
+    #[test]
+    fn incept_verify_test() {
+        let subject = CString::new("did:p2p:123456").unwrap();
+        let claim = CString::new(r#"{"personal": {"name": "Adem"}}"#).unwrap();
+        let mut out = ptr::null_mut();
+        let status = unsafe { sdt_incept(subject.as_ptr(), claim.as_ptr(), &mut out) };
+        assert_eq!(status, SdtStatus::Ok);
+        assert!(sdt_last_error().is_null());
+        let sdt = CString::new(take(out)).unwrap();
+
+        let status = unsafe { sdt_gen_proof(sdt.as_ptr(), &mut out) };
+        assert_eq!(status, SdtStatus::Ok);
+        let proof = CString::new(take(out)).unwrap();
+        assert_eq!(
+            unsafe { sdt_verify(sdt.as_ptr(), proof.as_ptr()) },
+            SdtStatus::Ok
+        );
+        let wrong = CString::new("0x1234").unwrap();
+        assert_eq!(
+            unsafe { sdt_verify(sdt.as_ptr(), wrong.as_ptr()) },
+            SdtStatus::VerificationError
+        );
+        assert!(!sdt_last_error().is_null());
+    }
+
+    #[test]
+    fn error_test() {
+        let mut out = ptr::null_mut();
+        let invalid = CString::new("{").unwrap();
+        let status = unsafe { sdt_gen_proof(invalid.as_ptr(), &mut out) };
+        assert_eq!(status, SdtStatus::JsonError);
+        assert!(out.is_null());
+        let status = unsafe { sdt_gen_proof(ptr::null(), &mut out) };
+        assert_eq!(status, SdtStatus::NullPointer);
+        let claim: SdtClaim = serde_json::from_str(r#"{"name": "Adem"}"#).unwrap();
+        let sdt = Sdt::new("did:p2p:123456", claim.to_node());
+        let sdt = CString::new(serde_json::to_string(&sdt).unwrap()).unwrap();
+        let query = CString::new("{\n}\n}").unwrap();
+        let status = unsafe { sdt_select(sdt.as_ptr(), query.as_ptr(), &mut out) };
+        assert_eq!(status, SdtStatus::QueryError);
+        let status = unsafe { sdt_dispatch(invalid.as_ptr(), ptr::null_mut()) };
+        assert_eq!(status, SdtStatus::NullPointer);
+        let utf8 = [0xffu8, 0];
+        let status = unsafe { sdt_dispatch(utf8.as_ptr() as *const c_char, &mut out) };
+        assert_eq!(status, SdtStatus::InvalidUtf8);
+    }
+}
@@ -0,0 +1,54 @@
+#include <assert.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "sdt.h"
+
+/* Every call is made before its result is asserted, so the calls stay with -DNDEBUG */
+int main(void) {
+  char *sdt = NULL;
+  char *mutated = NULL;
+  char *selected = NULL;
+  char *proof = NULL;
+  char *result = NULL;
+  enum SdtStatus status;
+
+  status = sdt_incept("did:p2p:123456",
+                      "{\"personal\": {\"name\": \"Adem\", \"surname\": \"Caglin\"}}",
+                      &sdt);
+  assert(status == SDT_STATUS_OK);
+  assert(sdt_last_error() == NULL);
+  status = sdt_mutate(sdt, "{\"personal\": {\"surname\": null}}", &mutated);
+  assert(status == SDT_STATUS_OK);
+  status = sdt_gen_proof(mutated, &proof);
+  assert(status == SDT_STATUS_OK);
+  assert(strncmp(proof, "0x", 2) == 0);
+
+  status = sdt_select(mutated, "{\n personal {\n name\n }\n}", &selected);
+  assert(status == SDT_STATUS_OK);
+  status = sdt_verify(selected, proof);
+  assert(status == SDT_STATUS_OK);
+  status = sdt_verify(sdt, proof);
+  assert(status == SDT_STATUS_VERIFICATION_ERROR);
+  assert(sdt_last_error() != NULL);
+
+  status = sdt_dispatch("{\"cmd\": \"Decode\", \"payload\": \"SDT1:%%%\"}", &result);
+  assert(status == SDT_STATUS_OK);
+  assert(strstr(result, "\"Error\"") != NULL);
+
+  char *untouched = NULL;
+  status = sdt_gen_proof("{", &untouched);
+  assert(status == SDT_STATUS_JSON_ERROR);
+  assert(untouched == NULL);
+  status = sdt_gen_proof(NULL, &untouched);
+  assert(status == SDT_STATUS_NULL_POINTER);
+
+  sdt_string_free(sdt);
+  sdt_string_free(mutated);
+  sdt_string_free(selected);
+  sdt_string_free(proof);
+  sdt_string_free(result);
+  sdt_string_free(NULL);
+  printf("ok\n");
+  return 0;
+}
@@ -0,0 +1,44 @@
+//! Compiles `tests/c/sdt_test.c` against the checked-in header and the static library.
+
+use std::{env, fs, path::PathBuf, process::Command};
+
+#[test]
+fn header_test() {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let generated = fs::read_to_string(PathBuf::from(env!("OUT_DIR")).join("sdt.h")).unwrap();
+    let checked_in = fs::read_to_string(manifest_dir.join("include/sdt.h")).unwrap();
+    assert!(
+        generated == checked_in,
+        "include/sdt.h is outdated, run `SDT_FFI_UPDATE_HEADER=1 cargo build -p sdt-ffi`"
+    );
+}
+
+#[test]
+fn c_program_test() {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    // target/<profile>/deps/c_program-<hash>, the library next to it is built for this test
+    // run while target/<profile>/libsdt_ffi.a is only refreshed by `cargo build`
+    let exe = env::current_exe().unwrap();
+    let deps_dir = exe.parent().unwrap();
+    let program = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("sdt_test");
+    let cc = env::var("CC").unwrap_or_else(|_| "cc".to_owned());
+
+    let status = Command::new(cc)
+        .arg(manifest_dir.join("tests/c/sdt_test.c"))
+        .arg("-I")
+        .arg(manifest_dir.join("include"))
+        .arg(deps_dir.join("libsdt_ffi.a"))
+        .args(["-lpthread", "-ldl", "-lm", "-o"])
+        .arg(&program)
+        .status()
+        .unwrap();
+    assert!(status.success(), "sdt_test.c doesn't compile");
+
+    let output = Command::new(&program).output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+}
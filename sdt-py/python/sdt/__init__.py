@@ -0,0 +1,61 @@
+"""Selective disclosure tries."""
+
+from ._sdt import (
+    AnchorError,
+    AuthorizationError,
+    CborError,
+    CompactError,
+    CredentialError,
+    DidError,
+    ForkError,
+    HexError,
+    InvalidKeyError,
+    InvalidSignatureError,
+    IoError,
+    JsonError,
+    PipelineError,
+    PositionError,
+    QueryError,
+    RevokedError,
+    SdJwtError,
+    Sdt,
+    SdtError,
+    SdtNode,
+    SessionError,
+    StatusError,
+    StoreError,
+    UnsupportedVersionError,
+    VerificationError,
+    WitnessError,
+    dispatch,
+)
+
+__all__ = [
+    "AnchorError",
+    "AuthorizationError",
+    "CborError",
+    "CompactError",
+    "CredentialError",
+    "DidError",
+    "ForkError",
+    "HexError",
+    "InvalidKeyError",
+    "InvalidSignatureError",
+    "IoError",
+    "JsonError",
+    "PipelineError",
+    "PositionError",
+    "QueryError",
+    "RevokedError",
+    "SdJwtError",
+    "Sdt",
+    "SdtError",
+    "SdtNode",
+    "SessionError",
+    "StatusError",
+    "StoreError",
+    "UnsupportedVersionError",
+    "VerificationError",
+    "WitnessError",
+    "dispatch",
+]
@@ -0,0 +1,63 @@
+from typing import Any, Dict, Optional, Union
+
+Value = Optional[Union[bool, int, float, str]]
+Claims = Dict[str, Union["Claims", Value]]
+
+class Sdt:
+    subject: str
+    version: int
+    def __init__(self, subject: str, claims: Claims) -> None: ...
+    @staticmethod
+    def from_json(data: str) -> "Sdt": ...
+    @staticmethod
+    def from_dict(value: Dict[str, Any]) -> "Sdt": ...
+    def to_json(self) -> str: ...
+    def to_dict(self) -> Dict[str, Any]: ...
+    def mutate(self, claims: Claims) -> "Sdt": ...
+    def select(self, query: str) -> "Sdt": ...
+    def gen_proof(self) -> str: ...
+    def verify(self, proof: str) -> bool: ...
+    def current_node(self) -> "SdtNode": ...
+    def claims(self) -> Claims: ...
+
+class SdtNode:
+    @staticmethod
+    def from_claims(claims: Claims) -> "SdtNode": ...
+    @staticmethod
+    def from_json(data: str) -> "SdtNode": ...
+    @staticmethod
+    def from_dict(value: Dict[str, Any]) -> "SdtNode": ...
+    def to_json(self) -> str: ...
+    def to_dict(self) -> Dict[str, Any]: ...
+    def to_claims(self) -> Claims: ...
+    def gen_proof(self) -> str: ...
+    def select(self, query: str) -> "SdtNode": ...
+
+def dispatch(input: str) -> str: ...
+
+class SdtError(Exception):
+    code: str
+    category: str
+class IoError(SdtError): ...
+class JsonError(SdtError): ...
+class HexError(SdtError): ...
+class CborError(SdtError): ...
+class VerificationError(SdtError): ...
+class UnsupportedVersionError(SdtError): ...
+class InvalidKeyError(SdtError): ...
+class InvalidSignatureError(SdtError): ...
+class SdJwtError(SdtError): ...
+class CredentialError(SdtError): ...
+class CompactError(SdtError): ...
+class QueryError(SdtError): ...
+class SessionError(SdtError): ...
+class StoreError(SdtError): ...
+class PositionError(SdtError): ...
+class AuthorizationError(SdtError): ...
+class WitnessError(SdtError): ...
+class StatusError(SdtError): ...
+class RevokedError(SdtError): ...
+class DidError(SdtError): ...
+class ForkError(SdtError): ...
+class PipelineError(SdtError): ...
+class AnchorError(SdtError): ...
@@ -0,0 +1,145 @@
+use pyo3::{create_exception, exceptions::PyException, prelude::*};
+
+create_exception!(
+    sdt,
+    SdtError,
+    PyException,
+    "Base class of every sdt error, `code` and `category` are the same as in `Error` results"
+);
+create_exception!(sdt, IoError, SdtError);
+create_exception!(
+    sdt,
+    JsonError,
+    SdtError,
+    "Invalid JSON or a value of the wrong shape"
+);
+create_exception!(sdt, HexError, SdtError);
+create_exception!(sdt, CborError, SdtError);
+create_exception!(
+    sdt,
+    VerificationError,
+    SdtError,
+    "The trie doesn't match the proof"
+);
+create_exception!(sdt, UnsupportedVersionError, SdtError);
+create_exception!(sdt, InvalidKeyError, SdtError);
+create_exception!(sdt, InvalidSignatureError, SdtError);
+create_exception!(sdt, SdJwtError, SdtError);
+create_exception!(sdt, CredentialError, SdtError);
+create_exception!(sdt, CompactError, SdtError);
+create_exception!(
+    sdt,
+    QueryError,
+    SdtError,
+    "A selection query that can't be parsed"
+);
+create_exception!(
+    sdt,
+    SessionError,
+    SdtError,
+    "An unknown handle, a full or missing session"
+);
+create_exception!(
+    sdt,
+    StoreError,
+    SdtError,
+    "An unknown subject, a write based on an outdated head or an unreadable store"
+);
+create_exception!(sdt, PositionError, SdtError);
+create_exception!(
+    sdt,
+    AuthorizationError,
+    SdtError,
+    "A mutation without a signature of the committed keys"
+);
+create_exception!(sdt, WitnessError, SdtError);
+create_exception!(sdt, StatusError, SdtError);
+create_exception!(
+    sdt,
+    RevokedError,
+    SdtError,
+    "The status bit of the trie is set"
+);
+create_exception!(sdt, DidError, SdtError);
+create_exception!(sdt, ForkError, SdtError);
+create_exception!(sdt, PipelineError, SdtError);
+create_exception!(sdt, AnchorError, SdtError);
+
+/// Raises the exception class of the variant with the `code` and `category` of the error,
+/// `Other` errors raise the base class.
+pub fn to_py_err(err: sdt::error::SdtError) -> PyErr {
+    use sdt::error::SdtError as E;
+
+    let msg = err.to_string();
+    let (code, category) = (err.code(), err.category().as_str());
+    let py_err = match err {
+        E::StdError(_) => IoError::new_err(msg),
+        E::SerdeJsonError(_) => JsonError::new_err(msg),
+        E::HexError(_) => HexError::new_err(msg),
+        E::CborError(_) => CborError::new_err(msg),
+        E::VerificationError { .. } => VerificationError::new_err(msg),
+        E::UnsupportedVersion(_) => UnsupportedVersionError::new_err(msg),
+        E::InvalidKey(_) => InvalidKeyError::new_err(msg),
+        E::InvalidSignature => InvalidSignatureError::new_err(msg),
+        E::SdJwtError(_) => SdJwtError::new_err(msg),
+        E::CredentialError(_) => CredentialError::new_err(msg),
+        E::CompactError(_) => CompactError::new_err(msg),
+        E::QueryError { .. } => QueryError::new_err(msg),
+        E::UnknownHandle(_) | E::SessionFull(_) | E::SessionRequired => SessionError::new_err(msg),
+        E::UnknownSubject(_) | E::StoreConflict { .. } | E::StoreError(_) => {
+            StoreError::new_err(msg)
+        }
+        E::PositionError { .. } => PositionError::new_err(msg),
+        E::AuthorizationError { .. } => AuthorizationError::new_err(msg),
+        E::WitnessError(_) => WitnessError::new_err(msg),
+        E::StatusError(_) => StatusError::new_err(msg),
+        E::Revoked { .. } => RevokedError::new_err(msg),
+        E::DidError(_) => DidError::new_err(msg),
+        E::ForkError(_) => ForkError::new_err(msg),
+        E::PipelineError(_) => PipelineError::new_err(msg),
+        E::AnchorError(_) => AnchorError::new_err(msg),
+        E::Other(_) => SdtError::new_err(msg),
+    };
+    Python::attach(|py| {
+        let value = py_err.value(py);
+        // attributes of a fresh exception instance can always be set
+        let _ = value.setattr("code", code);
+        let _ = value.setattr("category", category);
+    });
+    py_err
+}
+
+pub fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+    m.add("SdtError", py.get_type::<SdtError>())?;
+    m.add("IoError", py.get_type::<IoError>())?;
+    m.add("JsonError", py.get_type::<JsonError>())?;
+    m.add("HexError", py.get_type::<HexError>())?;
+    m.add("CborError", py.get_type::<CborError>())?;
+    m.add("VerificationError", py.get_type::<VerificationError>())?;
+    m.add(
+        "UnsupportedVersionError",
+        py.get_type::<UnsupportedVersionError>(),
+    )?;
+    m.add("InvalidKeyError", py.get_type::<InvalidKeyError>())?;
+    m.add(
+        "InvalidSignatureError",
+        py.get_type::<InvalidSignatureError>(),
+    )?;
+    m.add("SdJwtError", py.get_type::<SdJwtError>())?;
+    m.add("CredentialError", py.get_type::<CredentialError>())?;
+    m.add("CompactError", py.get_type::<CompactError>())?;
+    m.add("QueryError", py.get_type::<QueryError>())?;
+    m.add("SessionError", py.get_type::<SessionError>())?;
+    m.add("StoreError", py.get_type::<StoreError>())?;
+    m.add("PositionError", py.get_type::<PositionError>())?;
+    m.add("AuthorizationError", py.get_type::<AuthorizationError>())?;
+    m.add("WitnessError", py.get_type::<WitnessError>())?;
+    m.add("StatusError", py.get_type::<StatusError>())?;
+    m.add("RevokedError", py.get_type::<RevokedError>())?;
+    m.add("DidError", py.get_type::<DidError>())?;
+    m.add("ForkError", py.get_type::<ForkError>())?;
+    m.add("PipelineError", py.get_type::<PipelineError>())?;
+    m.add("AnchorError", py.get_type::<AnchorError>())?;
+    Ok(())
+}
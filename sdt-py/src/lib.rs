@@ -0,0 +1,249 @@
+//! Python bindings, claims and tries are exchanged as plain dicts.
+
+mod exceptions;
+
+use pyo3::prelude::*;
+use sdt::{node::SdtClaim, node::SdtNode, service::SdtService, Sdt};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::exceptions::to_py_err;
+
+/// A selective disclosure trie, methods return new tries instead of changing this one.
+#[pyclass(name = "Sdt", module = "sdt", frozen, eq)]
+#[derive(PartialEq, Debug, Clone)]
+pub struct PySdt(Sdt);
+
+#[pymethods]
+impl PySdt {
+    /// Creates a trie from inception claims
+    #[new]
+    fn new(subject: &str, claims: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let claims: SdtClaim = from_py(claims)?;
+        Ok(PySdt(Sdt::new(subject, claims.to_node())))
+    }
+
+    #[staticmethod]
+    fn from_json(data: &str) -> PyResult<Self> {
+        Ok(PySdt(from_json(data)?))
+    }
+
+    #[staticmethod]
+    fn from_dict(value: &Bound<'_, PyAny>) -> PyResult<Self> {
+        Ok(PySdt(from_py(value)?))
+    }
+
+    fn to_json(&self) -> PyResult<String> {
+        to_json(&self.0)
+    }
+
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        to_py(py, &self.0)
+    }
+
+    #[getter]
+    fn subject(&self) -> &str {
+        &self.0.subject
+    }
+
+    #[getter]
+    fn version(&self) -> u64 {
+        self.0.version
+    }
+
+    /// Appends a mutation of the claims
+    fn mutate(&self, claims: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let claims: SdtClaim = from_py(claims)?;
+        Ok(PySdt(self.0.clone().mutate(claims.to_node()).build()))
+    }
+
+    /// Keeps only the claims of the query, the rest is replaced by proofs
+    fn select(&self, query: &str) -> PyResult<Self> {
+        Ok(PySdt(self.0.select(query).map_err(to_py_err)?))
+    }
+
+    fn gen_proof(&self) -> PyResult<String> {
+        self.0.gen_proof().map_err(to_py_err)
+    }
+
+    /// Returns `False` when the proof doesn't match, raises when the trie is malformed.
+    fn verify(&self, proof: &str) -> PyResult<bool> {
+        match self.0.verify(proof) {
+            Err(sdt::error::SdtError::VerificationError { .. }) => Ok(false),
+            result => result.map_err(to_py_err),
+        }
+    }
+
+    /// Claims of all items merged into a single node
+    fn current_node(&self) -> PySdtNode {
+        PySdtNode(self.0.current_node())
+    }
+
+    /// Current claims without salts, hidden subtrees are left out
+    fn claims<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        to_py(py, &self.0.current_node().to_claim())
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Sdt(subject={:?}, version={}, items={})",
+            self.0.subject,
+            self.0.version,
+            self.0.items().len()
+        )
+    }
+}
+
+/// A node of salted values, proofs and inner nodes
+#[pyclass(name = "SdtNode", module = "sdt", frozen, eq)]
+#[derive(PartialEq, Debug, Clone)]
+pub struct PySdtNode(SdtNode);
+
+#[pymethods]
+impl PySdtNode {
+    /// Salts every value of the claims
+    #[staticmethod]
+    fn from_claims(claims: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let claims: SdtClaim = from_py(claims)?;
+        Ok(PySdtNode(claims.to_node()))
+    }
+
+    #[staticmethod]
+    fn from_json(data: &str) -> PyResult<Self> {
+        Ok(PySdtNode(from_json(data)?))
+    }
+
+    #[staticmethod]
+    fn from_dict(value: &Bound<'_, PyAny>) -> PyResult<Self> {
+        Ok(PySdtNode(from_py(value)?))
+    }
+
+    fn to_json(&self) -> PyResult<String> {
+        to_json(&self.0)
+    }
+
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        to_py(py, &self.0)
+    }
+
+    fn to_claims<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        to_py(py, &self.0.to_claim())
+    }
+
+    fn gen_proof(&self) -> PyResult<String> {
+        self.0.gen_proof().map_err(to_py_err)
+    }
+
+    fn select(&self, query: &str) -> PyResult<Self> {
+        let mut node = self.0.clone();
+        node.select(query).map_err(to_py_err)?;
+        Ok(PySdtNode(node))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "SdtNode(keys={:?})",
+            self.0.iter().map(|(k, _)| k).collect::<Vec<_>>()
+        )
+    }
+}
+
+/// Executes a serialized `SdtInput`, the result is a serialized `SdtResult`
+#[pyfunction]
+fn dispatch(input: &str) -> String {
+    SdtService(input.to_owned()).execute()
+}
+
+#[pymodule]
+#[pyo3(name = "_sdt")]
+fn sdt_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PySdt>()?;
+    m.add_class::<PySdtNode>()?;
+    m.add_function(wrap_pyfunction!(dispatch, m)?)?;
+    exceptions::register(m)
+}
+
+/// Python values pass through the `json` module, so dicts, lists and scalars are accepted.
+fn from_py<T: DeserializeOwned>(value: &Bound<'_, PyAny>) -> PyResult<T> {
+    let json = value.py().import("json")?.call_method1("dumps", (value,))?;
+    from_json(&json.extract::<String>()?)
+}
+
+fn to_py<'py, T: Serialize>(py: Python<'py>, value: &T) -> PyResult<Bound<'py, PyAny>> {
+    py.import("json")?.call_method1("loads", (to_json(value)?,))
+}
+
+fn from_json<T: DeserializeOwned>(data: &str) -> PyResult<T> {
+    serde_json::from_str(data).map_err(|e| to_py_err(e.into()))
+}
+
+fn to_json<T: Serialize>(value: &T) -> PyResult<String> {
+    serde_json::to_string(value).map_err(|e| to_py_err(e.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use pyo3::types::PyDict;
+
+    use super::*;
+
+    fn claims<'py>(py: Python<'py>, json: &str) -> Bound<'py, PyAny> {
+        py.import("json")
+            .unwrap()
+            .call_method1("loads", (json,))
+            .unwrap()
+    }
+
+    #[test]
+    fn sdt_test() -> PyResult<()> {
+        Python::attach(|py| {
+            let sdt = PySdt::new(
+                "did:p2p:123456",
+                &claims(py, r#"{"personal": {"name": "Adem", "surname": "Çağlın"}}"#),
+            )?;
+            let sdt = sdt.mutate(&claims(py, r#"{"personal": {"surname": null}}"#))?;
+            let proof = sdt.gen_proof()?;
+            assert!(sdt.verify(&proof)?);
+            assert!(!sdt.verify("0x1234")?);
+
+            let selected = sdt.select("{\n personal {\n name\n }\n}")?;
+            assert!(selected.verify(&proof)?);
+            let current = selected.claims(py)?;
+            let current = current.cast::<PyDict>()?;
+            assert_eq!(current.len(), 1);
+
+            let restored = PySdt::from_dict(&selected.to_dict(py)?)?;
+            assert!(restored == selected);
+            assert_eq!(PySdt::from_json(&sdt.to_json()?)?, sdt);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn exception_test() {
+        Python::attach(|py| {
+            let err = PySdt::from_json("{").unwrap_err();
+            assert!(err.is_instance_of::<exceptions::JsonError>(py));
+            assert!(err.is_instance_of::<exceptions::SdtError>(py));
+            let value = err.value(py);
+            assert_eq!(
+                value.getattr("code").unwrap().extract::<String>().unwrap(),
+                "invalid_json"
+            );
+            assert_eq!(
+                value
+                    .getattr("category")
+                    .unwrap()
+                    .extract::<String>()
+                    .unwrap(),
+                "input"
+            );
+            let err = PySdt::new("did:p2p:123456", &claims(py, "[1, 2]")).unwrap_err();
+            assert!(err.is_instance_of::<exceptions::JsonError>(py));
+            let sdt = PySdt::new("did:p2p:123456", &claims(py, r#"{"name": "Adem"}"#)).unwrap();
+            let err = sdt.select("{\n name\n}\n}").unwrap_err();
+            assert!(err.is_instance_of::<exceptions::QueryError>(py));
+            let code = err.value(py).getattr("code").unwrap();
+            assert_eq!(code.extract::<String>().unwrap(), "invalid_query");
+        })
+    }
+}
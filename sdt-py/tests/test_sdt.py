@@ -0,0 +1,45 @@
+"""Runs with `maturin develop && pytest tests` from `sdt-py`."""
+
+import json
+
+import pytest
+
+import sdt
+
+QUERY = "{\n personal {\n name\n }\n}"
+
+
+def incept():
+    return sdt.Sdt("did:p2p:123456", {"personal": {"name": "Adem", "surname": "Çağlın"}})
+
+
+def test_selection_and_verification():
+    trie = incept().mutate({"personal": {"surname": None}})
+    proof = trie.gen_proof()
+    selected = trie.select(QUERY)
+    assert selected.verify(proof)
+    assert not selected.verify("0x1234")
+    assert selected.claims() == {"personal": {"name": "Adem"}}
+    assert sdt.Sdt.from_json(selected.to_json()) == selected
+    assert sdt.Sdt.from_dict(trie.to_dict()) == trie
+
+
+def test_node():
+    node = sdt.SdtNode.from_claims({"name": "Adem", "age": 30})
+    assert node.to_claims() == {"name": "Adem", "age": 30}
+    assert node.select("{\n name\n}").gen_proof() == node.gen_proof()
+    node = sdt.SdtNode.from_claims({"name": "Adem", "height": 1.0})
+    assert node.select("{\n name\n}").gen_proof() == node.gen_proof()
+
+
+def test_exceptions():
+    with pytest.raises(sdt.JsonError):
+        sdt.Sdt.from_json("{")
+    with pytest.raises(sdt.SdtError):
+        sdt.Sdt("did:p2p:123456", [1, 2])
+    assert issubclass(sdt.VerificationError, sdt.SdtError)
+
+
+def test_dispatch():
+    result = json.loads(sdt.dispatch(json.dumps({"cmd": "Proof", "payload": incept().to_dict()})))
+    assert result["kind"] == "Proof"
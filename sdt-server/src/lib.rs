@@ -0,0 +1,242 @@
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    body::Bytes,
+    extract::{DefaultBodyLimit, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Router,
+};
+use sdt::{
+    error::{SdtError, SdtErrorCategory},
+    service::{SdtInput, SdtResult, SdtService, SdtSession},
+};
+use serde_json::{json, Value};
+
+const OPENAPI: &str = include_str!("../openapi.json");
+
+/// Default maximum size of a request body, in bytes
+pub const DEFAULT_BODY_LIMIT: usize = 1024 * 1024;
+/// Default maximum number of tries stored by handle
+pub const DEFAULT_MAX_DOCUMENTS: usize = 10_000;
+
+type SharedSession = Arc<Mutex<SdtSession>>;
+
+/// Routes of the api, `body_limit` bounds the size of every request body.
+pub fn app(body_limit: usize) -> Router {
+    app_with_session(body_limit, SdtSession::with_limit(DEFAULT_MAX_DOCUMENTS))
+}
+
+/// Routes of the api, tries stored by handle are kept in `session` and shared by all clients.
+pub fn app_with_session(body_limit: usize, session: SdtSession) -> Router {
+    Router::new()
+        .route("/inception", post(|s, body| command(s, "Inception", body)))
+        .route("/mutation", post(|s, body| command(s, "Mutation", body)))
+        .route("/selection", post(|s, body| command(s, "Selection", body)))
+        .route("/proof", post(|s, body| command(s, "Proof", body)))
+        .route(
+            "/verification",
+            post(|s, body| command(s, "Verification", body)),
+        )
+        .route("/dispatch", post(dispatch))
+        .route("/openapi.json", get(openapi))
+        .layer(DefaultBodyLimit::max(body_limit))
+        .with_state(Arc::new(Mutex::new(session)))
+}
+
+/// Wraps the body as the payload of a command, the service validates it.
+async fn command(State(session): State<SharedSession>, cmd: &str, body: Bytes) -> Response {
+    match serde_json::from_slice::<Value>(&body) {
+        Ok(payload) => execute(
+            &session,
+            json!({"cmd": cmd, "payload": payload}).to_string(),
+        ),
+        Err(err) => bad_request(err),
+    }
+}
+
+async fn dispatch(State(session): State<SharedSession>, body: Bytes) -> Response {
+    match serde_json::from_slice::<Value>(&body) {
+        Ok(input) => execute(&session, input.to_string()),
+        Err(err) => bad_request(err),
+    }
+}
+
+/// Malformed JSON is rejected before it reaches the service
+fn bad_request(err: serde_json::Error) -> Response {
+    let result = SdtResult::from(SdtError::from(err));
+    respond(StatusCode::BAD_REQUEST, json!(result).to_string())
+}
+
+async fn openapi() -> Response {
+    ([(header::CONTENT_TYPE, "application/json")], OPENAPI).into_response()
+}
+
+/// Only inputs that store or read tries by handle wait for the session
+fn execute(session: &SharedSession, input: String) -> Response {
+    let needs_session = serde_json::from_str::<SdtInput>(&input).is_ok_and(|i| i.needs_session());
+    let output = if needs_session {
+        // a panic can't leave the documents half written, so a poisoned lock is still usable
+        session
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .execute(&input)
+    } else {
+        SdtService(input).execute()
+    };
+    let status = match serde_json::from_str::<SdtResult>(&output) {
+        Ok(result) => status_of(&result),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    respond(status, output)
+}
+
+fn status_of(result: &SdtResult) -> StatusCode {
+    match result {
+        SdtResult::Error { code, category, .. } => match (code.as_str(), category) {
+            ("unknown_handle", _) => StatusCode::NOT_FOUND,
+            ("session_full", _) => StatusCode::SERVICE_UNAVAILABLE,
+            (_, SdtErrorCategory::Verification) => StatusCode::UNPROCESSABLE_ENTITY,
+            (_, SdtErrorCategory::Internal) => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        },
+        _ => StatusCode::OK,
+    }
+}
+
+fn respond(status: StatusCode, body: String) -> Response {
+    (status, [(header::CONTENT_TYPE, "application/json")], body).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, http::Request};
+    use http_body_util::BodyExt;
+    use sdt::{node::SdtClaim, Sdt};
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    async fn post_json(app: Router, uri: &str, body: String) -> (StatusCode, Value) {
+        let request = Request::post(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn inception_proof_verification_test() {
+        let app = app(DEFAULT_BODY_LIMIT);
+        let body = r#"{"subject": "did:p2p:123456", "claim": {"personal": {"name": "Adem"}}}"#;
+        let (status, sdt) = post_json(app.clone(), "/inception", body.to_owned()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(sdt["kind"], "Inception");
+
+        let (status, proof) = post_json(app.clone(), "/proof", sdt.to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        let body = serde_json::json!({"sdt": sdt, "proof": proof["proof"]});
+        let (status, result) = post_json(app.clone(), "/verification", body.to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(result["valid"], true);
+
+        let body = serde_json::json!({"sdt": sdt, "proof": "0x1234"});
+        let (status, result) = post_json(app, "/verification", body.to_string()).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(result["kind"], "Error");
+    }
+
+    #[tokio::test]
+    async fn dispatch_test() {
+        let claim: SdtClaim = serde_json::from_str(r#"{"personal": {"name": "Adem"}}"#).unwrap();
+        let sdt = Sdt::new("did:p2p:123456", claim.to_node());
+        let body = serde_json::json!({"cmd": "Selection", "payload": {"sdt": sdt, "query": "{\n personal {\n name\n }\n}"}});
+        let (status, result) =
+            post_json(app(DEFAULT_BODY_LIMIT), "/dispatch", body.to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(result["kind"], "Selection");
+
+        let (status, result) =
+            post_json(app(DEFAULT_BODY_LIMIT), "/mutation", "{".to_owned()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(result["kind"], "Error");
+        assert_eq!(result["code"], "invalid_json");
+
+        let (status, result) =
+            post_json(app(DEFAULT_BODY_LIMIT), "/mutation", "{}".to_owned()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(result["kind"], "Error");
+        assert_eq!(result["category"], "input");
+    }
+
+    #[tokio::test]
+    async fn body_limit_test() {
+        let body = format!(r#"{{"subject": "{}", "claim": {{}}}}"#, "a".repeat(2048));
+        let request = Request::post("/inception").body(Body::from(body)).unwrap();
+        let response = app(1024).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn openapi_test() {
+        let request = Request::get("/openapi.json").body(Body::empty()).unwrap();
+        let response = app(DEFAULT_BODY_LIMIT).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let spec: Value = serde_json::from_slice(&bytes).unwrap();
+        for path in [
+            "/inception",
+            "/mutation",
+            "/selection",
+            "/proof",
+            "/verification",
+            "/dispatch",
+        ] {
+            assert!(
+                spec["paths"].get(path).is_some(),
+                "{path} is not documented"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn handle_test() {
+        let app = app(DEFAULT_BODY_LIMIT);
+        let body = r#"{"subject": "did:p2p:123456", "claim": {"name": "Adem"}, "store": true}"#;
+        let (status, stored) = post_json(app.clone(), "/inception", body.to_owned()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(stored["kind"], "Stored");
+
+        let body = serde_json::json!({"handle": stored["handle"]});
+        let (_, proof) = post_json(app.clone(), "/proof", body.to_string()).await;
+        assert_eq!(proof["proof"], stored["proof"]);
+
+        let body = serde_json::json!({"cmd": "Evict", "payload": stored["handle"]});
+        let (status, _) = post_json(app.clone(), "/dispatch", body.to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        let body = serde_json::json!({"handle": stored["handle"]});
+        let (status, result) = post_json(app, "/proof", body.to_string()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(result["code"], "unknown_handle");
+    }
+
+    #[tokio::test]
+    async fn session_full_test() {
+        let app = app_with_session(DEFAULT_BODY_LIMIT, SdtSession::with_limit(1));
+        let body = r#"{"subject": "did:p2p:123456", "claim": {"name": "Adem"}, "store": true}"#;
+        let (status, _) = post_json(app.clone(), "/inception", body.to_owned()).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, result) = post_json(app.clone(), "/inception", body.to_owned()).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(result["code"], "session_full");
+
+        let body = r#"{"sdt": {"handle": "0x12"}, "query": "{\n name\n}"}"#;
+        let (status, _) = post_json(app, "/selection", body.to_owned()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
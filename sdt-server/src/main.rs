@@ -0,0 +1,26 @@
+use clap::Parser;
+use sdt::service::SdtSession;
+use sdt_server::{app_with_session, DEFAULT_BODY_LIMIT, DEFAULT_MAX_DOCUMENTS};
+
+/// HTTP api of the selective disclosure trie service.
+#[derive(Parser)]
+#[command(name = "sdt-server", version)]
+struct Args {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    addr: String,
+    /// Maximum size of a request body, in bytes
+    #[arg(long, default_value_t = DEFAULT_BODY_LIMIT)]
+    body_limit: usize,
+    /// Maximum number of tries stored by handle
+    #[arg(long, default_value_t = DEFAULT_MAX_DOCUMENTS)]
+    max_documents: usize,
+}
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    let args = Args::parse();
+    let listener = tokio::net::TcpListener::bind(&args.addr).await?;
+    let session = SdtSession::with_limit(args.max_documents);
+    axum::serve(listener, app_with_session(args.body_limit, session)).await
+}
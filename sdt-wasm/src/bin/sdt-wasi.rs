@@ -0,0 +1,13 @@
+//! Entry point for WASI runtimes, reads an `SdtInput` from stdin and writes the `SdtResult`
+//! to stdout, e.g. `wasmtime sdt-wasi.wasm < input.json`.
+
+use std::io::{self, Read};
+
+use sdt_wasm::dispatch;
+
+fn main() -> io::Result<()> {
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input)?;
+    println!("{}", dispatch(&input));
+    Ok(())
+}
@@ -1,10 +1,353 @@
 mod utils;
-use sdt::service::{SdtService};
+use js_sys::Reflect;
+use sdt::{
+    error::SdtError,
+    node::SdtClaim,
+    service::{SdtService, SdtSession},
+    utils::parse_query,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_wasm_bindgen::Serializer;
 use wasm_bindgen::prelude::*;
 
+#[wasm_bindgen(typescript_custom_section)]
+const TS_TYPES: &'static str = r#"
+export type SdtValueKind = null | boolean | number | string;
 
+export interface SdtClaim {
+  [key: string]: SdtClaim | SdtValueKind;
+}
+
+export interface SdtValueJson {
+  salt: string;
+  value: SdtValueKind;
+}
+
+export interface SdtNodeJson {
+  [key: string]: string | SdtValueJson | SdtNodeJson;
+}
+
+export interface SdtItemJson {
+  node: SdtNodeJson;
+  next?: SdtItemJson | null;
+  signatures?: SdtSignatureJson[];
+  /** Signatures of witnesses, `key_id` is the witness key */
+  receipts?: SdtSignatureJson[];
+}
+
+/** Signature of the chain proof after an item by a key of `keys/assertion_method` */
+export interface SdtSignatureJson {
+  key_id: string;
+  signature: string;
+}
+
+export interface SdtJson {
+  version: number;
+  subject: string;
+  inception: SdtItemJson;
+}
+
+export type SdtArmor = "base45" | "base64url";
+
+export type SdtInput =
+  | { cmd: "Inception"; payload: { subject: string; claim: SdtClaim; store?: boolean } }
+  | { cmd: "Mutation"; payload: { sdt: SdtRef; claim: SdtClaim } }
+  | { cmd: "Selection"; payload: { sdt: SdtRef; query: string } }
+  | { cmd: "Proof"; payload: SdtRef }
+  | { cmd: "Verification"; payload: { sdt: SdtRef; proof: string } }
+  | { cmd: "Encode"; payload: { sdt: SdtRef; armor?: SdtArmor } }
+  | { cmd: "Decode"; payload: string }
+  | { cmd: "Batch"; payload: SdtInput[] }
+  | { cmd: "Pipeline"; payload: { sdt?: SdtRef | null; steps: SdtStep[] } }
+  | { cmd: "Import"; payload: SdtJson }
+  | { cmd: "Export"; payload: string }
+  | { cmd: "Evict"; payload: string };
+
+/** A trie or the handle of a trie stored in a `Session` */
+export type SdtRef = SdtJson | { handle: string };
+
+export type SdtStep =
+  | { cmd: "Inception"; payload: { subject: string; claim: SdtClaim } }
+  | { cmd: "Mutation"; payload: { claim: SdtClaim } }
+  | { cmd: "Selection"; payload: { query: string } }
+  | { cmd: "Proof" }
+  | { cmd: "Verification"; payload: { proof: string } }
+  | { cmd: "Encode"; payload: { armor?: SdtArmor } }
+  | { cmd: "Decode"; payload: string };
+
+export type SdtResult =
+  | ({ kind: "Inception" | "Mutation" | "Selection" | "Decode" | "Export" } & SdtJson)
+  | { kind: "Stored"; handle: string; proof: string }
+  | { kind: "Evicted"; handle: string }
+  | { kind: "Proof"; proof: string }
+  | { kind: "Verification"; valid: boolean }
+  | { kind: "Encode"; data: string }
+  | { kind: "Error"; message: string; code: string; category: SdtErrorCategory; details?: SdtErrorDetails }
+  | { kind: "Batch" | "Pipeline"; results: SdtResult[] };
+
+/** Thrown by the classes, `code` and `category` are the same as in `Error` results */
+export interface SdtError extends Error {
+  code: string;
+  category: SdtErrorCategory;
+}
+
+export type SdtErrorCategory =
+  | "input"
+  | "verification"
+  | "query"
+  | "session"
+  | "store"
+  | "unsupported"
+  | "internal";
+
+/** Fields that apply to the error, `line` and `column` start at 1 */
+export interface SdtErrorDetails {
+  path?: string;
+  expected?: string;
+  actual?: string;
+  line?: number;
+  column?: number;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "SdtClaim")]
+    pub type Claim;
+
+    #[wasm_bindgen(typescript_type = "SdtJson")]
+    pub type SdtJson;
+
+    #[wasm_bindgen(typescript_type = "SdtNodeJson")]
+    pub type SdtNodeJson;
+}
+
+/// Runs once when the module is instantiated
+#[wasm_bindgen(start)]
+pub fn start() {
+    utils::set_panic_hook();
+}
+
+/// Executes a serialized `SdtInput`, the result is a serialized `SdtResult`
 #[wasm_bindgen]
 pub fn dispatch(input: &str) -> String {
     let service = SdtService(input.to_owned());
     service.execute()
 }
+
+/// Keeps tries between calls, so later commands can reference them by handle
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct Session(SdtSession);
+
+#[wasm_bindgen]
+impl Session {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Session {
+        Session::default()
+    }
+
+    /// Executes a serialized `SdtInput`, handles refer to tries of this session
+    pub fn dispatch(&mut self, input: &str) -> String {
+        self.0.execute(input)
+    }
+
+    /// Number of stored tries
+    #[wasm_bindgen(getter)]
+    pub fn size(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// A selective disclosure trie, methods return new tries instead of changing this one.
+#[wasm_bindgen(js_name = Sdt)]
+pub struct JsSdt(sdt::Sdt);
+
+#[wasm_bindgen(js_class = Sdt)]
+impl JsSdt {
+    /// Creates a trie from inception claims
+    #[wasm_bindgen(constructor)]
+    pub fn new(subject: &str, claim: Claim) -> Result<JsSdt, JsSdtError> {
+        let claim: SdtClaim = from_js(claim.into())?;
+        Ok(JsSdt(sdt::Sdt::new(subject, claim.to_node())))
+    }
+
+    #[wasm_bindgen(js_name = fromJSON)]
+    pub fn from_json(value: SdtJson) -> Result<JsSdt, JsSdtError> {
+        Ok(JsSdt(from_js(value.into())?))
+    }
+
+    /// Called by `JSON.stringify`
+    #[wasm_bindgen(js_name = toJSON)]
+    pub fn to_json(&self) -> Result<SdtJson, JsSdtError> {
+        to_js(&self.0)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn subject(&self) -> String {
+        self.0.subject.to_owned()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn version(&self) -> f64 {
+        self.0.version as f64
+    }
+
+    /// Appends a mutation of the claims
+    pub fn mutate(&self, claim: Claim) -> Result<JsSdt, JsSdtError> {
+        let claim: SdtClaim = from_js(claim.into())?;
+        Ok(JsSdt(self.0.clone().mutate(claim.to_node()).build()))
+    }
+
+    /// Keeps only the claims of the query, the rest is replaced by proofs
+    pub fn select(&self, query: &Query) -> Result<JsSdt, JsSdtError> {
+        Ok(JsSdt(self.0.select(&query.0)?))
+    }
+
+    #[wasm_bindgen(js_name = genProof)]
+    pub fn gen_proof(&self) -> Result<String, JsSdtError> {
+        Ok(self.0.gen_proof()?)
+    }
+
+    /// Throws when the trie is malformed, returns `false` when the proof doesn't match.
+    pub fn verify(&self, proof: &str) -> Result<bool, JsSdtError> {
+        match self.0.verify(proof) {
+            Err(SdtError::VerificationError { .. }) => Ok(false),
+            result => Ok(result?),
+        }
+    }
+
+    /// Claims of all items merged into a single node
+    #[wasm_bindgen(js_name = currentNode)]
+    pub fn current_node(&self) -> JsSdtNode {
+        JsSdtNode(self.0.current_node())
+    }
+}
+
+impl From<sdt::Sdt> for JsSdt {
+    fn from(sdt: sdt::Sdt) -> Self {
+        JsSdt(sdt)
+    }
+}
+
+impl From<JsSdt> for sdt::Sdt {
+    fn from(sdt: JsSdt) -> Self {
+        sdt.0
+    }
+}
+
+/// A node of salted values, proofs and inner nodes
+#[wasm_bindgen(js_name = SdtNode)]
+pub struct JsSdtNode(sdt::node::SdtNode);
+
+#[wasm_bindgen(js_class = SdtNode)]
+impl JsSdtNode {
+    /// Salts every value of the claims
+    #[wasm_bindgen(js_name = fromClaim)]
+    pub fn from_claim(claim: Claim) -> Result<JsSdtNode, JsSdtError> {
+        let claim: SdtClaim = from_js(claim.into())?;
+        Ok(JsSdtNode(claim.to_node()))
+    }
+
+    #[wasm_bindgen(js_name = fromJSON)]
+    pub fn from_json(value: SdtNodeJson) -> Result<JsSdtNode, JsSdtError> {
+        Ok(JsSdtNode(from_js(value.into())?))
+    }
+
+    #[wasm_bindgen(js_name = toJSON)]
+    pub fn to_json(&self) -> Result<SdtNodeJson, JsSdtError> {
+        to_js(&self.0)
+    }
+
+    /// Claims without salts, hidden subtrees are left out
+    #[wasm_bindgen(js_name = toClaim)]
+    pub fn to_claim(&self) -> Result<Claim, JsSdtError> {
+        to_js(&self.0.to_claim())
+    }
+
+    #[wasm_bindgen(js_name = genProof)]
+    pub fn gen_proof(&self) -> Result<String, JsSdtError> {
+        Ok(self.0.gen_proof()?)
+    }
+
+    pub fn select(&self, query: &Query) -> Result<JsSdtNode, JsSdtError> {
+        let mut node = self.0.clone();
+        node.select(&query.0)?;
+        Ok(JsSdtNode(node))
+    }
+}
+
+impl From<sdt::node::SdtNode> for JsSdtNode {
+    fn from(node: sdt::node::SdtNode) -> Self {
+        JsSdtNode(node)
+    }
+}
+
+impl From<JsSdtNode> for sdt::node::SdtNode {
+    fn from(node: JsSdtNode) -> Self {
+        node.0
+    }
+}
+
+/// A selection query, e.g. `{ personal { name } }` with one key per line
+#[wasm_bindgen]
+pub struct Query(String);
+
+#[wasm_bindgen]
+impl Query {
+    #[wasm_bindgen(constructor)]
+    pub fn new(query: &str) -> Query {
+        Query(query.to_owned())
+    }
+
+    /// Selected paths, e.g. `/personal/name/`, throws when the query is malformed
+    pub fn paths(&self) -> Result<Vec<String>, JsSdtError> {
+        Ok(parse_query(&self.0)?)
+    }
+
+    #[wasm_bindgen(js_name = toString)]
+    pub fn query(&self) -> String {
+        self.0.to_owned()
+    }
+}
+
+/// Thrown as an `Error` carrying the `code` and `category` of the `SdtError`
+#[derive(Debug)]
+pub struct JsSdtError(SdtError);
+
+impl From<SdtError> for JsSdtError {
+    fn from(err: SdtError) -> Self {
+        JsSdtError(err)
+    }
+}
+
+/// Values that don't fit the expected shape, like invalid JSON
+impl From<serde_wasm_bindgen::Error> for JsSdtError {
+    fn from(err: serde_wasm_bindgen::Error) -> Self {
+        JsSdtError(SdtError::SerdeJsonError(serde::de::Error::custom(err)))
+    }
+}
+
+impl From<JsSdtError> for JsValue {
+    fn from(err: JsSdtError) -> Self {
+        let js_err = js_sys::Error::new(&err.0.to_string());
+        // setting a property of a fresh `Error` can't fail
+        let _ = Reflect::set(&js_err, &"code".into(), &err.0.code().into());
+        let _ = Reflect::set(
+            &js_err,
+            &"category".into(),
+            &err.0.category().as_str().into(),
+        );
+        js_err.into()
+    }
+}
+
+fn from_js<T: DeserializeOwned>(value: JsValue) -> Result<T, JsSdtError> {
+    Ok(serde_wasm_bindgen::from_value(value)?)
+}
+
+/// Plain objects instead of `Map`s, so the result can be passed to `JSON.stringify`
+fn to_js<T: Serialize, R: JsCast>(value: &T) -> Result<R, JsSdtError> {
+    let value = value.serialize(&Serializer::json_compatible())?;
+    Ok(value.unchecked_into())
+}
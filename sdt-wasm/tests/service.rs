@@ -0,0 +1,188 @@
+//! Every `SdtInput` command through `dispatch`, runs natively with `cargo test` and in
+//! Node.js with `wasm-pack test --node`. The WASI build is checked with
+//! `cargo build -p sdt-wasm --bin sdt-wasi --target wasm32-wasip1`.
+
+use sdt_wasm::{dispatch, Session};
+use serde_json::{json, Value};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::wasm_bindgen_test;
+
+fn execute(cmd: &str, payload: Value) -> Value {
+    let input = json!({"cmd": cmd, "payload": payload});
+    serde_json::from_str(&dispatch(&input.to_string())).unwrap()
+}
+
+fn incept() -> Value {
+    let claim = json!({"personal": {"name": "Adem", "surname": "Çağlın"}, "documents": {"passport": "0x12"}});
+    execute(
+        "Inception",
+        json!({"subject": "did:p2p:123456", "claim": claim}),
+    )
+}
+
+/// Result without its `kind` tag, as the payload of the next command
+fn sdt_of(mut result: Value) -> Value {
+    result.as_object_mut().unwrap().remove("kind");
+    result
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn inception_mutation_test() {
+    let result = incept();
+    assert_eq!(result["kind"], "Inception");
+    assert_eq!(result["subject"], "did:p2p:123456");
+
+    let claim = json!({"personal": {"surname": null}});
+    let result = execute("Mutation", json!({"sdt": sdt_of(result), "claim": claim}));
+    assert_eq!(result["kind"], "Mutation");
+    assert!(result["inception"]["next"]["node"]["personal"]["surname"].is_object());
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn selection_proof_verification_test() {
+    let sdt = sdt_of(incept());
+    let proof = execute("Proof", sdt.clone());
+    assert_eq!(proof["kind"], "Proof");
+
+    let query = "{\n personal {\n name\n }\n}";
+    let selected = execute("Selection", json!({"sdt": sdt, "query": query}));
+    assert_eq!(selected["kind"], "Selection");
+    assert!(selected["inception"]["node"]["documents"].is_string());
+    assert!(selected["inception"]["node"]["personal"]["surname"].is_string());
+
+    let result = execute(
+        "Verification",
+        json!({"sdt": sdt_of(selected), "proof": proof["proof"]}),
+    );
+    assert_eq!(result, json!({"kind": "Verification", "valid": true}));
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn encode_decode_test() {
+    let sdt = sdt_of(incept());
+    for armor in ["base45", "base64url"] {
+        let encoded = execute("Encode", json!({"sdt": sdt, "armor": armor}));
+        assert_eq!(encoded["kind"], "Encode");
+        let decoded = execute("Decode", encoded["data"].clone());
+        assert_eq!(decoded["kind"], "Decode");
+        assert_eq!(sdt_of(decoded), sdt);
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn error_test() {
+    let result: Value = serde_json::from_str(&dispatch("{")).unwrap();
+    assert_eq!(result["kind"], "Error");
+    assert_eq!(result["code"], "invalid_json");
+    let result = execute("Proof", json!({"subject": "did:p2p:123456"}));
+    assert_eq!(result["kind"], "Error");
+    let result = execute("Decode", json!("SDT1:%%%"));
+    assert_eq!(result["kind"], "Error");
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn pipeline_test() {
+    let steps = json!([
+        {"cmd": "Inception", "payload": {"subject": "did:p2p:123456", "claim": {"personal": {"name": "Adem"}}}},
+        {"cmd": "Selection", "payload": {"query": "{\n personal {\n name\n }\n}"}},
+        {"cmd": "Encode", "payload": {}}
+    ]);
+    let result = execute("Pipeline", json!({ "steps": steps }));
+    assert_eq!(result["kind"], "Pipeline");
+    let kinds: Vec<&Value> = result["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| &r["kind"])
+        .collect();
+    assert_eq!(kinds, ["Inception", "Selection", "Encode"]);
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn batch_test() {
+    let sdt = sdt_of(incept());
+    let inputs = json!([
+        {"cmd": "Proof", "payload": sdt},
+        {"cmd": "Decode", "payload": "SDT1:%%%"}
+    ]);
+    let result = execute("Batch", inputs);
+    assert_eq!(result["kind"], "Batch");
+    assert_eq!(result["results"][0]["kind"], "Proof");
+    assert_eq!(result["results"][1]["kind"], "Error");
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn session_test() {
+    let mut session = Session::new();
+    let input = json!({"cmd": "Inception", "payload": {"subject": "did:p2p:123456", "claim": {"name": "Adem"}, "store": true}});
+    let stored: Value = serde_json::from_str(&session.dispatch(&input.to_string())).unwrap();
+    assert_eq!(stored["kind"], "Stored");
+    assert_eq!(session.size(), 1);
+
+    let input = json!({"cmd": "Proof", "payload": {"handle": stored["handle"]}});
+    let proof: Value = serde_json::from_str(&session.dispatch(&input.to_string())).unwrap();
+    assert_eq!(proof["proof"], stored["proof"]);
+
+    // the stateless dispatch doesn't know the handle
+    let result: Value = serde_json::from_str(&dispatch(&input.to_string())).unwrap();
+    assert_eq!(result["kind"], "Error");
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn import_export_evict_test() {
+    let mut session = Session::new();
+    let mut execute = |cmd: &str, payload: Value| -> Value {
+        let input = json!({"cmd": cmd, "payload": payload});
+        serde_json::from_str(&session.dispatch(&input.to_string())).unwrap()
+    };
+    let sdt = sdt_of(incept());
+    let stored = execute("Import", sdt.clone());
+    assert_eq!(stored["kind"], "Stored");
+    let exported = execute("Export", stored["handle"].clone());
+    assert_eq!(exported["kind"], "Export");
+    assert_eq!(sdt_of(exported), sdt);
+
+    let evicted = execute("Evict", stored["handle"].clone());
+    assert_eq!(
+        evicted,
+        json!({"kind": "Evicted", "handle": stored["handle"]})
+    );
+    let result = execute("Export", stored["handle"].clone());
+    assert_eq!(result["code"], "unknown_handle");
+    assert_eq!(session.size(), 0);
+}
+
+/// The `sdt-wasi` binary built for the host, the same code runs in WASI runtimes
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn wasi_binary_test() {
+    use std::{
+        io::Write,
+        process::{Command, Stdio},
+    };
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_sdt-wasi"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let input = json!({"cmd": "Inception", "payload": {"subject": "did:p2p:123456", "claim": {"name": "Adem"}}});
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.to_string().as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let result: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(result["kind"], "Inception");
+}
@@ -1,13 +1,57 @@
-//! Test suite for the Web and headless browsers.
+//! Test suite of the JS classes, runs in Node.js with `wasm-pack test --node` and in
+//! headless browsers with `WASM_BINDGEN_USE_BROWSER=1 wasm-pack test --headless --firefox`.
 
 #![cfg(target_arch = "wasm32")]
 
 extern crate wasm_bindgen_test;
+use js_sys::{Error, Reflect, JSON};
+use sdt_wasm::{JsSdt, JsSdtNode, Query};
+use wasm_bindgen::{JsCast, JsValue};
 use wasm_bindgen_test::*;
 
-wasm_bindgen_test_configure!(run_in_browser);
+fn parse<T: JsCast>(json: &str) -> T {
+    JSON::parse(json).unwrap().unchecked_into()
+}
+
+#[wasm_bindgen_test]
+fn classes_test() {
+    let sdt = JsSdt::new(
+        "did:p2p:123456",
+        parse(r#"{"personal": {"name": "Adem", "surname": "Çağlın"}}"#),
+    )
+    .unwrap();
+    let sdt = sdt
+        .mutate(parse(r#"{"personal": {"surname": null}}"#))
+        .unwrap();
+    let proof = sdt.gen_proof().unwrap();
+    assert!(sdt.verify(&proof).unwrap());
+
+    let query = Query::new("{\n personal {\n name\n }\n}");
+    assert_eq!(query.paths().unwrap(), vec!["/personal/name/".to_owned()]);
+    let selected = sdt.select(&query).unwrap();
+    assert!(selected.verify(&proof).unwrap());
+    assert!(!selected.verify("0x1234").unwrap());
+
+    let json = JSON::stringify(&selected.to_json().unwrap()).unwrap();
+    let restored = JsSdt::from_json(parse(&String::from(json))).unwrap();
+    assert_eq!(restored.gen_proof().unwrap(), proof);
+    assert_eq!(restored.subject(), "did:p2p:123456");
+
+    let node = JsSdtNode::from_claim(parse(r#"{"name": "Adem"}"#)).unwrap();
+    let claim = JSON::stringify(&node.to_claim().unwrap()).unwrap();
+    assert_eq!(String::from(claim), r#"{"name":"Adem"}"#);
+}
 
 #[wasm_bindgen_test]
-fn pass() {
-    assert_eq!(1 + 1, 2);
+fn error_test() {
+    let err: JsValue = JsSdt::new("did:p2p:123456", parse("[1, 2]"))
+        .err()
+        .unwrap()
+        .into();
+    assert!(err.is_instance_of::<Error>());
+    assert_eq!(Reflect::get(&err, &"code".into()).unwrap(), "invalid_json");
+    assert_eq!(Reflect::get(&err, &"category".into()).unwrap(), "input");
+    let err: JsValue = Query::new("{\n name\n}\n}").paths().unwrap_err().into();
+    assert_eq!(Reflect::get(&err, &"code".into()).unwrap(), "invalid_query");
+    assert!(JsSdt::from_json(parse(r#"{"subject": "did:p2p:123456"}"#)).is_err());
 }
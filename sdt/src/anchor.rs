@@ -0,0 +1,258 @@
+//! Publication of trie roots in a public source.
+//!
+//! Issuers publish the root of every version of a trie, verifiers check that a
+//! presented trie matches the latest published root of its subject.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::SdtError,
+    proof::{SdtHashSuite, SdtProof},
+    Sdt,
+};
+
+/// Previous hash of the first record of a log
+pub const GENESIS_HASH: &str = "0x0000000000000000000000000000000000000000000000000000000000000000";
+
+/// A published root, linked to the previous record of the log by its hash
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct AnchorRecord {
+    /// Position in the log, starting from 0
+    pub sequence: u64,
+    pub subject: String,
+    pub root: String,
+    pub timestamp: DateTime<Utc>,
+    pub previous: String,
+    pub hash: String,
+}
+
+impl AnchorRecord {
+    /// Digest of every other field of the record
+    pub fn gen_hash(&self) -> Result<String, SdtError> {
+        SdtProof::new()
+            .insert_i64("sequence", self.sequence as i64)
+            .insert_str("subject", &self.subject)
+            .insert_hex("root", &self.root)
+            .insert_str(
+                "timestamp",
+                &self.timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            )
+            .insert_hex("previous", &self.previous)
+            .digest_with(SdtHashSuite::JcsSha256)
+    }
+}
+
+/// A registry of published roots, e.g. a ledger or a transparency log.
+pub trait Anchor {
+    /// Publishes the root of the subject's latest version
+    fn publish(&self, subject: &str, root: &str) -> Result<AnchorRecord, SdtError>;
+    fn latest(&self, subject: &str) -> Result<Option<AnchorRecord>, SdtError>;
+    /// Records of the subject, oldest first
+    fn history(&self, subject: &str) -> Result<Vec<AnchorRecord>, SdtError>;
+
+    fn publish_sdt(&self, sdt: &Sdt) -> Result<AnchorRecord, SdtError> {
+        self.publish(&sdt.subject, &sdt.gen_proof()?)
+    }
+
+    /// The last record of the subject published at or before `time`
+    fn root_at(&self, subject: &str, time: DateTime<Utc>) -> Result<AnchorRecord, SdtError> {
+        self.history(subject)?
+            .into_iter()
+            .rev()
+            .find(|record| record.timestamp <= time)
+            .ok_or_else(|| {
+                SdtError::AnchorError(format!("{subject} has no root published before {time}"))
+            })
+    }
+
+    /// Checks that a (selected, truncated) trie matches the root of its subject at `time`,
+    /// e.g. the credential as of the date of a contract
+    fn verify_sdt_at(&self, sdt: &Sdt, time: DateTime<Utc>) -> Result<AnchorRecord, SdtError> {
+        let record = self.root_at(&sdt.subject, time)?;
+        sdt.verify(&record.root)?;
+        Ok(record)
+    }
+
+    /// Checks that a (selected) trie matches the latest root of its subject
+    fn verify_sdt(&self, sdt: &Sdt) -> Result<AnchorRecord, SdtError> {
+        let latest = self
+            .latest(&sdt.subject)?
+            .ok_or_else(|| SdtError::UnknownSubject(sdt.subject.to_owned()))?;
+        sdt.verify(&latest.root)?;
+        Ok(latest)
+    }
+}
+
+/// Append-only log of records as JSON lines in a local file.
+///
+/// Every record commits to the previous one, reads check the whole chain so an edited,
+/// removed or reordered record is detected.
+#[derive(Debug)]
+pub struct FileAnchorLog {
+    path: PathBuf,
+}
+
+impl FileAnchorLog {
+    /// The file is created by the first publication
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_owned(),
+        }
+    }
+
+    /// Every record of the log after checking the hash chain
+    pub fn records(&self) -> Result<Vec<AnchorRecord>, SdtError> {
+        let data = match fs::read_to_string(&self.path) {
+            Ok(data) => data,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => return Err(err.into()),
+        };
+        let mut records: Vec<AnchorRecord> = vec![];
+        for line in data.lines().filter(|line| !line.is_empty()) {
+            let record: AnchorRecord = serde_json::from_str(line)?;
+            let previous = records.last().map_or(GENESIS_HASH, |r| &r.hash);
+            if record.sequence != records.len() as u64
+                || record.previous != previous
+                || record.hash != record.gen_hash()?
+            {
+                return Err(SdtError::AnchorError(format!(
+                    "Record {} breaks the hash chain",
+                    records.len()
+                )));
+            }
+            records.push(record);
+        }
+        Ok(records)
+    }
+}
+
+impl Anchor for FileAnchorLog {
+    fn publish(&self, subject: &str, root: &str) -> Result<AnchorRecord, SdtError> {
+        let mut file: File = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.lock()?;
+        let records = self.records()?;
+        let mut record = AnchorRecord {
+            sequence: records.len() as u64,
+            subject: subject.to_owned(),
+            root: root.to_owned(),
+            timestamp: Utc::now(),
+            previous: records
+                .last()
+                .map_or(GENESIS_HASH.to_owned(), |r| r.hash.to_owned()),
+            hash: String::new(),
+        };
+        record.hash = record.gen_hash()?;
+        writeln!(file, "{}", serde_json::to_string(&record)?)?;
+        file.sync_all()?;
+        Ok(record)
+    }
+
+    fn latest(&self, subject: &str) -> Result<Option<AnchorRecord>, SdtError> {
+        Ok(self.history(subject)?.pop())
+    }
+
+    fn history(&self, subject: &str) -> Result<Vec<AnchorRecord>, SdtError> {
+        Ok(self
+            .records()?
+            .into_iter()
+            .filter(|record| record.subject == subject)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::node::{SdtClaim, SdtNode};
+
+    use super::*;
+
+    fn create_sdt(subject: &str) -> Result<Sdt, SdtError> {
+        let claim: SdtClaim = serde_json::from_str(r#"{"personal": {"name": "Adem"}}"#)?;
+        Ok(Sdt::new(subject, claim.to_node()))
+    }
+
+    #[test]
+    fn publish_verify_test() -> Result<(), SdtError> {
+        let dir = tempfile::tempdir()?;
+        let log = FileAnchorLog::new(dir.path().join("anchors.jsonl"));
+        let mut sdt = create_sdt("did:p2p:123456")?;
+        assert!(matches!(
+            log.verify_sdt(&sdt),
+            Err(SdtError::UnknownSubject(_))
+        ));
+        let first = log.publish_sdt(&sdt)?;
+        assert_eq!(first.previous, GENESIS_HASH);
+        log.publish_sdt(&create_sdt("did:p2p:654321")?)?;
+
+        let selected = sdt.select("{\n personal {\n name\n }\n}")?;
+        assert_eq!(log.verify_sdt(&selected)?, first);
+        // a mutation makes the previous version outdated once it is published
+        sdt.mutate(SdtNode::new());
+        let second = log.publish_sdt(&sdt)?;
+        assert_eq!(second.sequence, 2);
+        assert!(matches!(
+            log.verify_sdt(&selected),
+            Err(SdtError::VerificationError { .. })
+        ));
+        log.verify_sdt(&sdt)?;
+        assert_eq!(log.history("did:p2p:123456")?, vec![first, second]);
+        assert_eq!(log.records()?.len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn historical_root_test() -> Result<(), SdtError> {
+        let dir = tempfile::tempdir()?;
+        let log = FileAnchorLog::new(dir.path().join("anchors.jsonl"));
+        let mut sdt = create_sdt("did:p2p:123456")?;
+        let first = log.publish_sdt(&sdt)?;
+        sdt.mutate(SdtNode::new());
+        let second = log.publish_sdt(&sdt)?;
+
+        // the trie as of the first publication
+        let contract_date = first.timestamp;
+        let presented = sdt.truncate(0)?.select("{\n personal {\n name\n }\n}")?;
+        assert_eq!(log.verify_sdt_at(&presented, contract_date)?, first);
+        assert!(log.verify_sdt(&presented).is_err());
+        assert!(log.verify_sdt_at(&sdt, contract_date).is_err());
+        assert_eq!(log.verify_sdt_at(&sdt, second.timestamp)?, second);
+        assert!(matches!(
+            log.root_at("did:p2p:123456", contract_date - chrono::Duration::days(1)),
+            Err(SdtError::AnchorError(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn tamper_test() -> Result<(), SdtError> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("anchors.jsonl");
+        let log = FileAnchorLog::new(&path);
+        let first = log.publish_sdt(&create_sdt("did:p2p:123456")?)?;
+        log.publish_sdt(&create_sdt("did:p2p:654321")?)?;
+
+        let data = fs::read_to_string(&path)?;
+        let other = create_sdt("did:p2p:123456")?.gen_proof()?;
+        fs::write(&path, data.replace(&first.root, &other))?;
+        assert!(matches!(log.records(), Err(SdtError::AnchorError(_))));
+
+        // dropping the first record breaks the chain as well
+        let second_line = data.lines().nth(1).unwrap();
+        fs::write(&path, format!("{second_line}\n"))?;
+        assert!(matches!(
+            log.latest("did:p2p:654321"),
+            Err(SdtError::AnchorError(_))
+        ));
+        Ok(())
+    }
+}
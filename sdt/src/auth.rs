@@ -0,0 +1,388 @@
+//! Authorization of mutations by keys committed in the trie, in the style of KERI key events.
+//!
+//! Keys are multibase Ed25519 values under `keys/assertion_method`. Once the trie declares a
+//! key, every mutation must be signed by one of the current keys. `keys/next` may hold
+//! commitments to the next keys, a mutation that changes the keys must then introduce only
+//! committed keys and be signed by one of them, so a leaked current key can't rotate.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cbor::Cbor,
+    did::{decode_ed25519_multibase, ed25519_multibase},
+    error::SdtError,
+    node::{SdtNode, SdtNodeKind},
+    signer::{Ed25519Verifier, SdtSigner, SdtVerifier, ED25519_ALGORITHM},
+    utils::{digest_str, from_hex_str, to_hex_str},
+    value::{SdtValue, SdtValueKind},
+    Sdt, SdtItem,
+};
+
+pub const KEYS: &str = "keys";
+pub const ASSERTION_METHOD: &str = "assertion_method";
+pub const NEXT_KEYS: &str = "next";
+
+/// Signature of the chain proof after a mutation by the key `key_id` of `keys/assertion_method`
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+pub struct SdtSignature {
+    pub key_id: String,
+    pub signature: String,
+}
+
+impl SdtSignature {
+    pub fn to_cbor(&self) -> Result<Cbor, SdtError> {
+        Ok(Cbor::Map(vec![
+            (Cbor::text("key_id"), Cbor::text(&self.key_id)),
+            (
+                Cbor::text("signature"),
+                Cbor::Bytes(from_hex_str(&self.signature)?),
+            ),
+        ]))
+    }
+
+    pub fn from_cbor(item: &Cbor) -> Result<Self, SdtError> {
+        match (item.get("key_id"), item.get("signature")) {
+            (Some(Cbor::Text(key_id)), Some(Cbor::Bytes(signature))) => Ok(SdtSignature {
+                key_id: key_id.to_owned(),
+                signature: to_hex_str(signature),
+            }),
+            _ => Err(SdtError::CborError("Invalid signature".to_owned())),
+        }
+    }
+}
+
+/// Value of an Ed25519 public key under `keys/assertion_method`
+pub fn ed25519_key(public: &[u8; 32]) -> String {
+    ed25519_multibase(public)
+}
+
+/// Value to put under `keys/next` before rotating to `key`
+pub fn key_commitment(key: &str) -> String {
+    digest_str(key)
+}
+
+/// Signs the chain proof after a mutation, each signer needs the name of its key as key id
+pub(crate) fn sign(proof: &str, signers: &[&dyn SdtSigner]) -> Result<Vec<SdtSignature>, SdtError> {
+    let msg = from_hex_str(proof)?;
+    signers
+        .iter()
+        .map(|signer| {
+            if signer.algorithm() != ED25519_ALGORITHM {
+                return Err(SdtError::InvalidKey(
+                    "Mutations are signed with Ed25519 keys".to_owned(),
+                ));
+            }
+            let key_id = signer.key_id().ok_or_else(|| {
+                SdtError::InvalidKey("Signer of a mutation needs a key id".to_owned())
+            })?;
+            Ok(SdtSignature {
+                key_id,
+                signature: to_hex_str(signer.sign(&msg)?),
+            })
+        })
+        .collect()
+}
+
+/// Checks every mutation against the keys of the state before it.
+///
+/// Mutations whose keys are hidden by a selection can't be checked, they pass unless
+/// `strict` is set, which also requires the inception to disclose its keys.
+pub(crate) fn check(sdt: &Sdt, strict: bool) -> Result<(), SdtError> {
+    let items = sdt.items();
+    let history = sdt.history()?;
+    let inception_keys = key_set(&sdt.inception.node, ASSERTION_METHOD);
+    if strict && (inception_keys.hidden || inception_keys.values.is_empty()) {
+        return Err(unauthorized(0, "Inception doesn't disclose its keys"));
+    }
+    let mut state = sdt.inception.node.clone();
+    for (position, item) in items.into_iter().enumerate().skip(1) {
+        check_mutation(&state, item, &history[position], strict)
+            .map_err(|message| unauthorized(position, message))?;
+        state.merge(&item.node);
+    }
+    Ok(())
+}
+
+fn check_mutation(
+    state: &SdtNode,
+    item: &SdtItem,
+    proof: &str,
+    strict: bool,
+) -> Result<(), &'static str> {
+    let commitments = key_set(state, NEXT_KEYS);
+    let signing_keys = if changes_keys(&item.node) && !commitments.is_empty() {
+        let introduced = key_set(&item.node, ASSERTION_METHOD);
+        if commitments.hidden || introduced.hidden {
+            return pass_unless(strict, "Key commitments are hidden");
+        }
+        if introduced
+            .values
+            .values()
+            .any(|key| !commitments.contains(&key_commitment(key)))
+        {
+            return Err("Rotation introduces a key that wasn't committed");
+        }
+        let mut next = state.clone();
+        next.merge(&item.node);
+        let mut keys = key_set(&next, ASSERTION_METHOD);
+        keys.values
+            .retain(|_, key| commitments.contains(&key_commitment(key)));
+        if keys.is_empty() {
+            return Err("Rotation leaves no committed key to sign it");
+        }
+        keys
+    } else {
+        let keys = key_set(state, ASSERTION_METHOD);
+        if keys.is_empty() {
+            // no keys declared yet, anyone may mutate
+            return Ok(());
+        }
+        keys
+    };
+    if is_signed(item, &signing_keys.values, proof) {
+        return Ok(());
+    }
+    if signing_keys.hidden {
+        return pass_unless(strict, "Signing keys are hidden");
+    }
+    Err("Mutation isn't signed by a current key")
+}
+
+/// Whether a node declares keys under `keys/assertion_method`, hidden ones included
+pub(crate) fn has_keys(node: &SdtNode) -> bool {
+    !key_set(node, ASSERTION_METHOD).is_empty()
+}
+
+fn pass_unless(strict: bool, message: &'static str) -> Result<(), &'static str> {
+    if strict {
+        Err(message)
+    } else {
+        Ok(())
+    }
+}
+
+fn unauthorized(position: usize, message: &str) -> SdtError {
+    SdtError::AuthorizationError {
+        position,
+        message: message.to_owned(),
+    }
+}
+
+/// Disclosed keys of `keys/<name>` by key name, `hidden` when some of them are proofs
+#[derive(Default)]
+struct KeySet {
+    values: BTreeMap<String, String>,
+    hidden: bool,
+}
+
+impl KeySet {
+    fn is_empty(&self) -> bool {
+        self.values.is_empty() && !self.hidden
+    }
+
+    fn contains(&self, value: &str) -> bool {
+        self.values.values().any(|v| v == value)
+    }
+}
+
+fn key_set(node: &SdtNode, name: &str) -> KeySet {
+    let mut set = KeySet::default();
+    let keys = match node.get(KEYS) {
+        Some(SdtNodeKind::Node(keys)) => keys,
+        Some(SdtNodeKind::Proof(_)) => {
+            set.hidden = true;
+            return set;
+        }
+        _ => return set,
+    };
+    let keys = match keys.get(name) {
+        Some(SdtNodeKind::Node(keys)) => keys,
+        Some(SdtNodeKind::Proof(_)) => {
+            set.hidden = true;
+            return set;
+        }
+        _ => return set,
+    };
+    for (key_id, kind) in keys.iter() {
+        match kind {
+            SdtNodeKind::Value(SdtValue {
+                value: SdtValueKind::String(key),
+                ..
+            }) => {
+                set.values.insert(key_id.to_owned(), key.to_owned());
+            }
+            SdtNodeKind::Proof(_) => set.hidden = true,
+            // null removes a key, other values can't sign
+            _ => {}
+        }
+    }
+    set
+}
+
+/// Whether a mutation touches the current or the committed keys
+fn changes_keys(node: &SdtNode) -> bool {
+    match node.get(KEYS) {
+        Some(SdtNodeKind::Node(keys)) => {
+            keys.get(ASSERTION_METHOD).is_some() || keys.get(NEXT_KEYS).is_some()
+        }
+        Some(_) => true,
+        None => false,
+    }
+}
+
+fn is_signed(item: &SdtItem, keys: &BTreeMap<String, String>, proof: &str) -> bool {
+    let Ok(msg) = from_hex_str(proof) else {
+        return false;
+    };
+    item.signatures.iter().any(|signature| {
+        keys.get(&signature.key_id)
+            .is_some_and(|key| verify_signature(key, &msg, &signature.signature).is_ok())
+    })
+}
+
+pub(crate) fn verify_signature(key: &str, msg: &[u8], signature: &str) -> Result<(), SdtError> {
+    let public = decode_ed25519_multibase(key)?;
+    Ed25519Verifier::from_public(&public)?.verify(
+        ED25519_ALGORITHM,
+        None,
+        msg,
+        &from_hex_str(signature)?,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::signer::Ed25519Signer;
+
+    use super::*;
+
+    fn key_node(name: &str, keys: &[(&str, String)]) -> SdtNode {
+        let mut node = SdtNode::new();
+        for (key_id, key) in keys {
+            node.add_str_value(key_id, key);
+        }
+        SdtNode::new().add_node(name, node).build()
+    }
+
+    fn create_sdt(signer: &Ed25519Signer, next: &Ed25519Signer) -> Sdt {
+        let mut keys = key_node(
+            ASSERTION_METHOD,
+            &[("key_1", ed25519_key(&signer.public_key()))],
+        );
+        keys.merge(&key_node(
+            NEXT_KEYS,
+            &[("key_2", key_commitment(&ed25519_key(&next.public_key())))],
+        ));
+        let personal = SdtNode::new().add_str_value("name", "Adem").build();
+        let root = SdtNode::new()
+            .add_node("personal", personal)
+            .add_node(KEYS, keys)
+            .build();
+        Sdt::new("did:p2p:123456", root)
+    }
+
+    fn name_mutation(name: &str) -> SdtNode {
+        let personal = SdtNode::new().add_str_value("name", name).build();
+        SdtNode::new().add_node("personal", personal).build()
+    }
+
+    fn assert_unauthorized(result: Result<bool, SdtError>, expected: usize) {
+        match result {
+            Err(SdtError::AuthorizationError { position, .. }) => assert_eq!(position, expected),
+            other => panic!("Mutation {expected} should be unauthorized, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn signed_mutation_test() -> Result<(), SdtError> {
+        let signer = Ed25519Signer::new().with_key_id("key_1");
+        let mut sdt = create_sdt(&signer, &Ed25519Signer::new());
+        sdt.mutate_signed(name_mutation("Ahmet"), &[&signer])?;
+        assert!(sdt.verify(&sdt.gen_proof()?)?);
+        assert!(sdt.verify_authorized(&sdt.gen_proof()?)?);
+
+        let mut unsigned = sdt.clone();
+        unsigned.mutate(name_mutation("Mehmet"));
+        assert_unauthorized(unsigned.verify(&unsigned.gen_proof()?), 2);
+
+        let foreign = Ed25519Signer::new().with_key_id("key_1");
+        let mut forged = sdt.clone();
+        forged.mutate_signed(name_mutation("Mehmet"), &[&foreign])?;
+        assert_unauthorized(forged.verify(&forged.gen_proof()?), 2);
+
+        let anonymous = Ed25519Signer::new();
+        assert!(sdt
+            .clone()
+            .mutate_signed(name_mutation("Mehmet"), &[&anonymous])
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn rotation_test() -> Result<(), SdtError> {
+        let signer = Ed25519Signer::new().with_key_id("key_1");
+        let next = Ed25519Signer::new().with_key_id("key_2");
+        let mut sdt = create_sdt(&signer, &next);
+
+        let uncommitted = Ed25519Signer::new();
+        let mut keys = key_node(
+            ASSERTION_METHOD,
+            &[("key_3", ed25519_key(&uncommitted.public_key()))],
+        );
+        let mut hijacked = sdt.clone();
+        hijacked.mutate_signed(
+            SdtNode::new().add_node(KEYS, keys.clone()).build(),
+            &[&signer],
+        )?;
+        assert_unauthorized(hijacked.verify(&hijacked.gen_proof()?), 1);
+
+        keys = key_node(
+            ASSERTION_METHOD,
+            &[("key_2", ed25519_key(&next.public_key()))],
+        );
+        keys.merge(&key_node(
+            NEXT_KEYS,
+            &[(
+                "key_3",
+                key_commitment(&ed25519_key(&uncommitted.public_key())),
+            )],
+        ));
+        let mut removed = SdtNode::new();
+        removed.add_null_value("key_1");
+        keys.merge(&SdtNode::new().add_node(ASSERTION_METHOD, removed).build());
+        sdt.mutate_signed(SdtNode::new().add_node(KEYS, keys).build(), &[&next])?;
+        assert!(sdt.verify(&sdt.gen_proof()?)?);
+
+        let mut stale = sdt.clone();
+        stale.mutate_signed(name_mutation("Ahmet"), &[&signer])?;
+        assert_unauthorized(stale.verify(&stale.gen_proof()?), 2);
+        sdt.mutate_signed(name_mutation("Ahmet"), &[&next])?;
+        assert!(sdt.verify_authorized(&sdt.gen_proof()?)?);
+        Ok(())
+    }
+
+    #[test]
+    fn hidden_keys_test() -> Result<(), SdtError> {
+        let signer = Ed25519Signer::new().with_key_id("key_1");
+        let mut sdt = create_sdt(&signer, &Ed25519Signer::new());
+        sdt.mutate(name_mutation("Ahmet"));
+        let proof = sdt.gen_proof()?;
+        let selected = sdt.select("{\n personal {\n name\n }\n}")?;
+        assert_unauthorized(selected.verify(&proof), 0);
+        assert_unauthorized(selected.verify_authorized(&proof), 0);
+        assert!(selected.verify_lenient(&proof)?);
+        let selected = sdt
+            .select("{\n personal {\n name\n }\n keys {\n assertion_method {\n key_1\n }\n }\n}")?;
+        assert_unauthorized(selected.verify(&proof), 1);
+
+        let keyless = Sdt::new("did:p2p:123456", name_mutation("Adem"))
+            .mutate(name_mutation("Ahmet"))
+            .build();
+        assert!(keyless.verify(&keyless.gen_proof()?)?);
+        assert_unauthorized(keyless.verify_authorized(&keyless.gen_proof()?), 0);
+        assert!(keyless.verify_lenient(&keyless.gen_proof()?)?);
+        Ok(())
+    }
+}
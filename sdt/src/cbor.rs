@@ -0,0 +1,363 @@
+use crate::error::SdtError;
+
+/// A CBOR data item (RFC 8949) restricted to the types used by sdt.
+#[derive(PartialEq, Debug, Clone)]
+pub enum Cbor {
+    Unsigned(u64),
+    /// Negative integer `-1 - n`
+    Negative(u64),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<Cbor>),
+    Map(Vec<(Cbor, Cbor)>),
+    Bool(bool),
+    Null,
+    Float(f64),
+}
+
+const MAJOR_UNSIGNED: u8 = 0;
+const MAJOR_NEGATIVE: u8 = 1;
+const MAJOR_BYTES: u8 = 2;
+const MAJOR_TEXT: u8 = 3;
+const MAJOR_ARRAY: u8 = 4;
+const MAJOR_MAP: u8 = 5;
+const MAJOR_SIMPLE: u8 = 7;
+const MAX_DEPTH: usize = 256;
+
+impl Cbor {
+    pub fn text(s: &str) -> Self {
+        Self::Text(s.to_owned())
+    }
+
+    /// Encodes the item with the core deterministic encoding requirements (RFC 8949 §4.2.1):
+    /// preferred (shortest) serialization, definite lengths and map keys sorted by the
+    /// bytewise order of their encodings.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = vec![];
+        self.write(&mut out);
+        out
+    }
+
+    /// Decodes a single item and rejects any encoding that is not deterministic.
+    pub fn decode(bytes: &[u8]) -> Result<Self, SdtError> {
+        let mut reader = CborReader {
+            bytes,
+            pos: 0,
+            depth: 0,
+        };
+        let item = reader.read()?;
+        if reader.pos != bytes.len() {
+            return Err(SdtError::CborError("Trailing bytes".to_owned()));
+        }
+        if item.encode() != bytes {
+            return Err(SdtError::CborError(
+                "Encoding is not deterministic".to_owned(),
+            ));
+        }
+        Ok(item)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Cbor> {
+        match self {
+            Self::Map(entries) => entries
+                .iter()
+                .find(|(k, _)| matches!(k, Cbor::Text(t) if t == key))
+                .map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        match self {
+            Self::Unsigned(n) => write_head(out, MAJOR_UNSIGNED, *n),
+            Self::Negative(n) => write_head(out, MAJOR_NEGATIVE, *n),
+            Self::Bytes(b) => {
+                write_head(out, MAJOR_BYTES, b.len() as u64);
+                out.extend_from_slice(b);
+            }
+            Self::Text(s) => {
+                write_head(out, MAJOR_TEXT, s.len() as u64);
+                out.extend_from_slice(s.as_bytes());
+            }
+            Self::Array(items) => {
+                write_head(out, MAJOR_ARRAY, items.len() as u64);
+                for item in items {
+                    item.write(out);
+                }
+            }
+            Self::Map(entries) => {
+                let mut encoded: Vec<(Vec<u8>, Vec<u8>)> = entries
+                    .iter()
+                    .map(|(k, v)| (k.encode(), v.encode()))
+                    .collect();
+                encoded.sort_by(|a, b| a.0.cmp(&b.0));
+                write_head(out, MAJOR_MAP, encoded.len() as u64);
+                for (k, v) in encoded {
+                    out.extend_from_slice(&k);
+                    out.extend_from_slice(&v);
+                }
+            }
+            Self::Bool(false) => out.push(0xf4),
+            Self::Bool(true) => out.push(0xf5),
+            Self::Null => out.push(0xf6),
+            Self::Float(f) => write_float(out, *f),
+        }
+    }
+}
+
+fn write_head(out: &mut Vec<u8>, major: u8, n: u64) {
+    let major = major << 5;
+    if n < 24 {
+        out.push(major | n as u8);
+    } else if n <= u8::MAX as u64 {
+        out.push(major | 24);
+        out.push(n as u8);
+    } else if n <= u16::MAX as u64 {
+        out.push(major | 25);
+        out.extend_from_slice(&(n as u16).to_be_bytes());
+    } else if n <= u32::MAX as u64 {
+        out.push(major | 26);
+        out.extend_from_slice(&(n as u32).to_be_bytes());
+    } else {
+        out.push(major | 27);
+        out.extend_from_slice(&n.to_be_bytes());
+    }
+}
+
+fn write_float(out: &mut Vec<u8>, f: f64) {
+    if let Some(half) = f64_to_f16(f) {
+        out.push(0xf9);
+        out.extend_from_slice(&half.to_be_bytes());
+    } else if (f as f32) as f64 == f {
+        out.push(0xfa);
+        out.extend_from_slice(&(f as f32).to_be_bytes());
+    } else {
+        out.push(0xfb);
+        out.extend_from_slice(&f.to_be_bytes());
+    }
+}
+
+/// Half precision bits of `f` if the conversion is lossless.
+fn f64_to_f16(f: f64) -> Option<u16> {
+    if f.is_nan() {
+        return Some(0x7e00);
+    }
+    let sign: u16 = if f.is_sign_negative() { 0x8000 } else { 0 };
+    let abs = f.abs();
+    if abs == 0.0 {
+        return Some(sign);
+    }
+    if abs.is_infinite() {
+        return Some(sign | 0x7c00);
+    }
+    let exp = abs.log2().floor() as i32;
+    if !(-24..=15).contains(&exp) {
+        return None;
+    }
+    let bits = if exp < -14 {
+        // subnormal, value = mantissa * 2^-24
+        let mantissa = abs * 2f64.powi(24);
+        if mantissa.fract() != 0.0 {
+            return None;
+        }
+        mantissa as u16
+    } else {
+        let mantissa = (abs / 2f64.powi(exp) - 1.0) * 1024.0;
+        if mantissa.fract() != 0.0 {
+            return None;
+        }
+        (((exp + 15) as u16) << 10) | mantissa as u16
+    };
+    Some(sign | bits)
+}
+
+fn f16_to_f64(half: u16) -> f64 {
+    let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exp = ((half >> 10) & 0x1f) as i32;
+    let mantissa = (half & 0x3ff) as f64;
+    let abs = match exp {
+        0 => mantissa * 2f64.powi(-24),
+        0x1f if mantissa == 0.0 => f64::INFINITY,
+        0x1f => f64::NAN,
+        _ => (1.0 + mantissa / 1024.0) * 2f64.powi(exp - 15),
+    };
+    sign * abs
+}
+
+struct CborReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> CborReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SdtError> {
+        if self.bytes.len() - self.pos < len {
+            return Err(SdtError::CborError("Unexpected end of input".to_owned()));
+        }
+        let slice = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(slice)
+    }
+
+    fn read_uint(&mut self, info: u8) -> Result<u64, SdtError> {
+        let n = match info {
+            0..=23 => info as u64,
+            24 => self.take(1)?[0] as u64,
+            25 => u16::from_be_bytes(self.take(2)?.try_into().unwrap()) as u64,
+            26 => u32::from_be_bytes(self.take(4)?.try_into().unwrap()) as u64,
+            27 => u64::from_be_bytes(self.take(8)?.try_into().unwrap()),
+            _ => {
+                return Err(SdtError::CborError(format!(
+                    "Unsupported additional information {info}"
+                )))
+            }
+        };
+        Ok(n)
+    }
+
+    fn read_len(&mut self, info: u8) -> Result<usize, SdtError> {
+        let len = self.read_uint(info)? as usize;
+        if len > self.bytes.len() - self.pos {
+            return Err(SdtError::CborError("Length exceeds input".to_owned()));
+        }
+        Ok(len)
+    }
+
+    fn read(&mut self) -> Result<Cbor, SdtError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(SdtError::CborError("Nesting is too deep".to_owned()));
+        }
+        let item = self.read_item()?;
+        self.depth -= 1;
+        Ok(item)
+    }
+
+    fn read_item(&mut self) -> Result<Cbor, SdtError> {
+        let initial = self.take(1)?[0];
+        let major = initial >> 5;
+        let info = initial & 0x1f;
+        let item = match major {
+            MAJOR_UNSIGNED => Cbor::Unsigned(self.read_uint(info)?),
+            MAJOR_NEGATIVE => Cbor::Negative(self.read_uint(info)?),
+            MAJOR_BYTES => {
+                let len = self.read_len(info)?;
+                Cbor::Bytes(self.take(len)?.to_vec())
+            }
+            MAJOR_TEXT => {
+                let len = self.read_len(info)?;
+                let text = std::str::from_utf8(self.take(len)?)
+                    .map_err(|e| SdtError::CborError(e.to_string()))?;
+                Cbor::Text(text.to_owned())
+            }
+            MAJOR_ARRAY => {
+                let len = self.read_len(info)?;
+                let mut items = Vec::with_capacity(len);
+                for _ in 0..len {
+                    items.push(self.read()?);
+                }
+                Cbor::Array(items)
+            }
+            MAJOR_MAP => {
+                let len = self.read_len(info)?;
+                let mut entries: Vec<(Cbor, Cbor)> = Vec::with_capacity(len);
+                for _ in 0..len {
+                    let key = self.read()?;
+                    // keys are sorted once the encoding is deterministic, a duplicate follows its twin
+                    if entries.last().is_some_and(|(last, _)| *last == key) {
+                        return Err(SdtError::CborError("Duplicate map key".to_owned()));
+                    }
+                    entries.push((key, self.read()?));
+                }
+                Cbor::Map(entries)
+            }
+            MAJOR_SIMPLE => match info {
+                20 => Cbor::Bool(false),
+                21 => Cbor::Bool(true),
+                22 => Cbor::Null,
+                25 => Cbor::Float(f16_to_f64(u16::from_be_bytes(
+                    self.take(2)?.try_into().unwrap(),
+                ))),
+                26 => Cbor::Float(f32::from_be_bytes(self.take(4)?.try_into().unwrap()) as f64),
+                27 => Cbor::Float(f64::from_be_bytes(self.take(8)?.try_into().unwrap())),
+                _ => {
+                    return Err(SdtError::CborError(format!(
+                        "Unsupported simple value {info}"
+                    )))
+                }
+            },
+            _ => {
+                return Err(SdtError::CborError(format!(
+                    "Unsupported major type {major}"
+                )))
+            }
+        };
+        Ok(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_test() {
+        // Examples from RFC 8949 Appendix A
+        let cases = vec![
+            (Cbor::Unsigned(0), "00"),
+            (Cbor::Unsigned(24), "1818"),
+            (Cbor::Unsigned(1000000), "1a000f4240"),
+            (Cbor::Negative(999), "3903e7"),
+            (Cbor::Float(0.0), "f90000"),
+            (Cbor::Float(-0.0), "f98000"),
+            (Cbor::Float(1.5), "f93e00"),
+            (Cbor::Float(65504.0), "f97bff"),
+            (Cbor::Float(100000.0), "fa47c35000"),
+            (Cbor::Float(5.960464477539063e-8), "f90001"),
+            (Cbor::Float(1.1), "fb3ff199999999999a"),
+            (Cbor::Text("ü".to_owned()), "62c3bc"),
+            (Cbor::Bytes(vec![1, 2, 3, 4]), "4401020304"),
+            (Cbor::Null, "f6"),
+            (
+                Cbor::Map(vec![
+                    (Cbor::text("a"), Cbor::Array(vec![Cbor::Bool(true)])),
+                    (Cbor::text("bb"), Cbor::Unsigned(2)),
+                ]),
+                "a2616181f562626202",
+            ),
+        ];
+        for (item, expected) in cases {
+            let encoded = item.encode();
+            assert_eq!(hex::encode(&encoded), expected);
+            assert_eq!(Cbor::decode(&encoded).unwrap(), item);
+        }
+        // map keys are sorted by their encoding whatever the order they are given in
+        let unsorted = Cbor::Map(vec![
+            (Cbor::text("bb"), Cbor::Unsigned(2)),
+            (Cbor::text("a"), Cbor::Array(vec![Cbor::Bool(true)])),
+        ]);
+        assert_eq!(hex::encode(unsorted.encode()), "a2616181f562626202");
+    }
+
+    #[test]
+    fn decode_non_deterministic_test() {
+        // 10 encoded in two bytes
+        assert!(Cbor::decode(&hex::decode("180a").unwrap()).is_err());
+        // unsorted map keys
+        assert!(Cbor::decode(&hex::decode("a2616202616101").unwrap()).is_err());
+        // duplicate map keys
+        assert!(Cbor::decode(&hex::decode("a2616101616101").unwrap()).is_err());
+        // trailing bytes and truncated input
+        assert!(Cbor::decode(&hex::decode("0000").unwrap()).is_err());
+        assert!(Cbor::decode(&hex::decode("4401").unwrap()).is_err());
+        assert!(Cbor::decode(&[0x81; 1000]).is_err());
+        assert_eq!(
+            Cbor::decode(&hex::decode("a2616101616202").unwrap()).unwrap(),
+            Cbor::Map(vec![
+                (Cbor::text("a"), Cbor::Unsigned(1)),
+                (Cbor::text("b"), Cbor::Unsigned(2))
+            ])
+        );
+    }
+}
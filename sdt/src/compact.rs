@@ -0,0 +1,355 @@
+use std::io::{Read, Write};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth::SdtSignature,
+    cbor::Cbor,
+    error::SdtError,
+    node::{SdtNode, SdtNodeKind},
+    utils::{from_hex_str, to_hex_str},
+    value::SdtValue,
+    Sdt, SdtItem,
+};
+
+/// Format 2 added the signatures and receipts parts, format 1 presentations still decode
+const FORMAT_VERSION: u64 = 0x2;
+const UNSIGNED_FORMAT_VERSION: u64 = 0x1;
+/// QR alphanumeric friendly prefix of base45 armor
+const BASE45_PREFIX: &str = "SDT1:";
+const BASE64URL_PREFIX: &str = "sdt1.";
+/// Upper bound of an inflated presentation, guards against decompression bombs
+const MAX_INFLATED_SIZE: u64 = 1024 * 1024;
+
+/// Text armor of a compact presentation
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SdtArmor {
+    /// For QR codes in alphanumeric mode
+    #[default]
+    Base45,
+    Base64Url,
+}
+
+/// Encodes a trie as deflated CBOR with raw digests and a key dictionary, suitable for NFC.
+///
+/// The pre-image is `[format, version, subject, keys, items]` where `keys` holds every
+/// node key once and nodes are maps from key indexes to proofs, `[salt, value]` arrays
+/// or inner nodes. Signed tries append `signatures` and witnessed tries `receipts` too,
+/// each holds one array of `[key_id, signature]` pairs per item.
+pub fn to_compact_bytes(sdt: &Sdt) -> Result<Vec<u8>, SdtError> {
+    let items = sdt.items();
+    let mut keys: Vec<String> = vec![];
+    for item in &items {
+        collect_keys(&item.node, &mut keys);
+    }
+    keys.sort();
+    keys.dedup();
+    let mut encoded_items = vec![];
+    for item in &items {
+        encoded_items.push(encode_node(&item.node, &keys)?);
+    }
+    let mut parts = vec![
+        Cbor::Unsigned(FORMAT_VERSION),
+        Cbor::Unsigned(sdt.version),
+        Cbor::text(&sdt.subject),
+        Cbor::Array(keys.iter().map(|k| Cbor::text(k)).collect()),
+        Cbor::Array(encoded_items),
+    ];
+    let receipted = items.iter().any(|item| !item.receipts.is_empty());
+    if receipted || items.iter().any(|item| !item.signatures.is_empty()) {
+        parts.push(encode_signatures(
+            items.iter().map(|item| &item.signatures),
+        )?);
+    }
+    if receipted {
+        parts.push(encode_signatures(items.iter().map(|item| &item.receipts))?);
+    }
+    let cbor = Cbor::Array(parts);
+    let mut encoder = DeflateEncoder::new(vec![], Compression::best());
+    encoder.write_all(&cbor.encode())?;
+    Ok(encoder.finish()?)
+}
+
+pub fn from_compact_bytes(bytes: &[u8]) -> Result<Sdt, SdtError> {
+    let mut inflated = vec![];
+    DeflateDecoder::new(bytes)
+        .take(MAX_INFLATED_SIZE + 1)
+        .read_to_end(&mut inflated)
+        .map_err(|e| compact_error(&e.to_string()))?;
+    if inflated.len() as u64 > MAX_INFLATED_SIZE {
+        return Err(compact_error("Presentation is too large"));
+    }
+    let parts = match Cbor::decode(&inflated)? {
+        Cbor::Array(parts) => parts,
+        _ => return Err(compact_error("Presentation should be an array")),
+    };
+    let (parts, signed) = parts.split_at(parts.len().min(5));
+    match parts {
+        [Cbor::Unsigned(format), Cbor::Unsigned(version), Cbor::Text(subject), Cbor::Array(keys), Cbor::Array(items)]
+            if (*format == FORMAT_VERSION && signed.len() <= 2)
+                || (*format == UNSIGNED_FORMAT_VERSION && signed.is_empty()) =>
+        {
+            let mut signed = signed
+                .iter()
+                .map(|part| decode_signatures(part, items.len()))
+                .collect::<Result<Vec<_>, SdtError>>()?;
+            signed.resize_with(2, || vec![vec![]; items.len()]);
+            let (mut signatures, mut receipts) = (signed.remove(0), signed.remove(0));
+            let keys = keys
+                .iter()
+                .map(|k| match k {
+                    Cbor::Text(k) => Ok(k.to_owned()),
+                    _ => Err(compact_error("Key should be text")),
+                })
+                .collect::<Result<Vec<String>, SdtError>>()?;
+            let mut next: Option<Box<SdtItem>> = None;
+            for item in items.iter().rev() {
+                next = Some(Box::new(SdtItem {
+                    node: decode_node(item, &keys)?,
+                    next,
+                    signatures: signatures.pop().unwrap_or_default(),
+                    receipts: receipts.pop().unwrap_or_default(),
+                }));
+            }
+            let inception = next.ok_or_else(|| compact_error("Inception is missing"))?;
+            Ok(Sdt {
+                version: *version,
+                subject: subject.to_owned(),
+                inception: *inception,
+            })
+        }
+        _ => Err(compact_error("Unsupported presentation format")),
+    }
+}
+
+/// Compact bytes with a text armor, e.g. `SDT1:<base45>` for QR codes
+pub fn encode(sdt: &Sdt, armor: SdtArmor) -> Result<String, SdtError> {
+    let bytes = to_compact_bytes(sdt)?;
+    let text = match armor {
+        SdtArmor::Base45 => format!("{}{}", BASE45_PREFIX, base45::encode(bytes)),
+        SdtArmor::Base64Url => format!("{}{}", BASE64URL_PREFIX, URL_SAFE_NO_PAD.encode(bytes)),
+    };
+    Ok(text)
+}
+
+/// Decodes an armored presentation, the armor is detected from its prefix.
+pub fn decode(text: &str) -> Result<Sdt, SdtError> {
+    let text = text.trim();
+    let bytes = if let Some(data) = text.strip_prefix(BASE45_PREFIX) {
+        base45::decode(data).map_err(|e| compact_error(&e.to_string()))?
+    } else if let Some(data) = text.strip_prefix(BASE64URL_PREFIX) {
+        URL_SAFE_NO_PAD
+            .decode(data)
+            .map_err(|e| compact_error(&e.to_string()))?
+    } else {
+        return Err(compact_error("Unknown armor"));
+    };
+    from_compact_bytes(&bytes)
+}
+
+fn collect_keys(node: &SdtNode, keys: &mut Vec<String>) {
+    for (k, v) in node.iter() {
+        keys.push(k.to_owned());
+        if let SdtNodeKind::Node(inner) = v {
+            collect_keys(inner, keys);
+        }
+    }
+}
+
+fn encode_node(node: &SdtNode, keys: &[String]) -> Result<Cbor, SdtError> {
+    let mut entries = vec![];
+    for (k, v) in node.iter() {
+        let index = keys
+            .binary_search(k)
+            .map_err(|_| SdtError::CompactError(format!("Key {k} is not in the key table")))?;
+        let value = match v {
+            SdtNodeKind::Proof(p) => Cbor::Bytes(from_hex_str(p)?),
+            SdtNodeKind::Value(val) => val.to_cbor()?,
+            SdtNodeKind::Node(inner) => encode_node(inner, keys)?,
+        };
+        entries.push((Cbor::Unsigned(index as u64), value));
+    }
+    Ok(Cbor::Map(entries))
+}
+
+fn decode_node(item: &Cbor, keys: &[String]) -> Result<SdtNode, SdtError> {
+    let entries = match item {
+        Cbor::Map(entries) => entries,
+        _ => return Err(compact_error("Node should be a map")),
+    };
+    let mut node = SdtNode::new();
+    for (k, v) in entries {
+        let key = match k {
+            Cbor::Unsigned(index) => keys
+                .get(*index as usize)
+                .ok_or_else(|| compact_error("Unknown key index"))?,
+            _ => return Err(compact_error("Key should be an index")),
+        };
+        match v {
+            Cbor::Bytes(p) => node.add_proof(key, &to_hex_str(p)),
+            Cbor::Array(_) => node.add_sdt_value(key, SdtValue::from_cbor(v)?),
+            Cbor::Map(_) => node.add_node(key, decode_node(v, keys)?),
+            _ => return Err(compact_error("Invalid node kind")),
+        };
+    }
+    Ok(node)
+}
+
+/// One array of `[key_id, signature]` pairs per item
+fn encode_signatures<'a>(
+    items: impl Iterator<Item = &'a Vec<SdtSignature>>,
+) -> Result<Cbor, SdtError> {
+    let mut encoded = vec![];
+    for signatures in items {
+        let mut pairs = vec![];
+        for s in signatures {
+            pairs.push(Cbor::Array(vec![
+                Cbor::text(&s.key_id),
+                Cbor::Bytes(from_hex_str(&s.signature)?),
+            ]));
+        }
+        encoded.push(Cbor::Array(pairs));
+    }
+    Ok(Cbor::Array(encoded))
+}
+
+fn decode_signatures(part: &Cbor, len: usize) -> Result<Vec<Vec<SdtSignature>>, SdtError> {
+    match part {
+        Cbor::Array(items) if items.len() == len => items
+            .iter()
+            .map(|item| match item {
+                Cbor::Array(pairs) => pairs.iter().map(decode_signature).collect(),
+                _ => Err(compact_error("Signatures should be an array")),
+            })
+            .collect(),
+        _ => Err(compact_error("Signatures should match the items")),
+    }
+}
+
+fn decode_signature(pair: &Cbor) -> Result<SdtSignature, SdtError> {
+    match pair {
+        Cbor::Array(pair) => match pair.as_slice() {
+            [Cbor::Text(key_id), Cbor::Bytes(signature)] => Ok(SdtSignature {
+                key_id: key_id.to_owned(),
+                signature: to_hex_str(signature),
+            }),
+            _ => Err(compact_error("Invalid signature")),
+        },
+        _ => Err(compact_error("Invalid signature")),
+    }
+}
+
+fn compact_error(msg: &str) -> SdtError {
+    SdtError::CompactError(msg.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        auth::{ed25519_key, ASSERTION_METHOD, KEYS},
+        node::SdtClaim,
+        signer::Ed25519Signer,
+    };
+
+    fn create_sdt() -> Result<Sdt, SdtError> {
+        let claim: SdtClaim = serde_json::from_str(
+            r#"{"personal": {"name": "Adem", "surname": "Çağlın", "age_over_18": true}, "documents": {"passport": "0x12"}}"#,
+        )?;
+        let mutation: SdtClaim = serde_json::from_str(r#"{"personal": {"surname": null}}"#)?;
+        Ok(Sdt::new("did:p2p:123456", claim.to_node())
+            .mutate(mutation.to_node())
+            .build())
+    }
+
+    #[test]
+    fn round_trip_test() -> Result<(), SdtError> {
+        let sdt = create_sdt()?;
+        let proof = sdt.gen_proof()?;
+        let selected = sdt.select("{\n personal {\n age_over_18\n }\n}")?;
+        for armor in [SdtArmor::Base45, SdtArmor::Base64Url] {
+            let text = encode(&selected, armor)?;
+            let decoded = decode(&text)?;
+            assert_eq!(decoded, selected);
+            assert!(decoded.verify(&proof)?);
+        }
+        assert_eq!(from_compact_bytes(&to_compact_bytes(&sdt)?)?, sdt);
+        Ok(())
+    }
+
+    #[test]
+    fn signed_round_trip_test() -> Result<(), SdtError> {
+        let signer = Ed25519Signer::new().with_key_id("key_1");
+        let key = SdtNode::new()
+            .add_str_value("key_1", &ed25519_key(&signer.public_key()))
+            .build();
+        let keys = SdtNode::new().add_node(ASSERTION_METHOD, key).build();
+        let mut sdt = Sdt::new(
+            "did:p2p:123456",
+            SdtNode::new().add_node(KEYS, keys).build(),
+        );
+        sdt.mutate_signed(
+            SdtNode::new().add_str_value("name", "Adem").build(),
+            &[&signer],
+        )?;
+        let decoded = from_compact_bytes(&to_compact_bytes(&sdt)?)?;
+        assert_eq!(decoded, sdt);
+        assert!(decoded.verify_authorized(&sdt.gen_proof()?)?);
+        assert_eq!(Sdt::from_cbor(&sdt.to_cbor()?)?, sdt);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_test() -> Result<(), SdtError> {
+        assert!(decode("HC1:6BFOXN").is_err());
+        assert!(decode("SDT1:%%%").is_err());
+        let mut bytes = to_compact_bytes(&create_sdt()?)?;
+        bytes.truncate(bytes.len() / 2);
+        assert_eq!(
+            from_compact_bytes(&bytes).unwrap_err().code(),
+            "invalid_compact"
+        );
+        assert_eq!(decode("sdt1.AAAA").unwrap_err().code(), "invalid_compact");
+        let node = create_sdt()?.inception.node;
+        assert!(matches!(
+            encode_node(&node, &["personal".to_owned()]),
+            Err(SdtError::CompactError(_))
+        ));
+        Ok(())
+    }
+
+    /// Compact bytes of `sdt` labelled with another format version
+    fn with_format(sdt: &Sdt, format: u64) -> Result<Vec<u8>, SdtError> {
+        let mut inflated = vec![];
+        DeflateDecoder::new(to_compact_bytes(sdt)?.as_slice()).read_to_end(&mut inflated)?;
+        let mut parts = match Cbor::decode(&inflated)? {
+            Cbor::Array(parts) => parts,
+            _ => unreachable!(),
+        };
+        parts[0] = Cbor::Unsigned(format);
+        let mut encoder = DeflateEncoder::new(vec![], Compression::best());
+        encoder.write_all(&Cbor::Array(parts).encode())?;
+        Ok(encoder.finish()?)
+    }
+
+    #[test]
+    fn format_version_test() -> Result<(), SdtError> {
+        let sdt = create_sdt()?;
+        assert_eq!(from_compact_bytes(&with_format(&sdt, 1)?)?, sdt);
+        assert!(from_compact_bytes(&with_format(&sdt, 3)?).is_err());
+
+        let signer = Ed25519Signer::new().with_key_id("key_1");
+        let mut signed = sdt.clone();
+        signed.mutate_signed(SdtNode::new(), &[&signer])?;
+        assert_eq!(from_compact_bytes(&with_format(&signed, 2)?)?, signed);
+        // format 1 has no signatures part
+        assert!(matches!(
+            from_compact_bytes(&with_format(&signed, 1)?),
+            Err(SdtError::CompactError(_))
+        ));
+        Ok(())
+    }
+}
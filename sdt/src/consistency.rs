@@ -0,0 +1,154 @@
+//! Proofs that a later chain proof extends an earlier one.
+//!
+//! Each item proof commits to the previous proof and the root of the item node, so the
+//! node roots between two positions are enough to get from the earlier proof to the
+//! later one. Node roots don't reveal claims.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{error::SdtError, proof::SdtHashSuite, proof::SdtProof, Sdt};
+
+/// Links the proof of the chain at item `from` to the proof at item `to`
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct SdtConsistencyProof {
+    pub version: u64, // See SdtHashSuite
+    pub from: usize,
+    pub to: usize,
+    /// Node roots of the items after `from` up to `to`
+    pub roots: Vec<String>,
+}
+
+impl SdtConsistencyProof {
+    /// Positions start at 0 for the inception, selected tries give the same proof.
+    pub fn new(sdt: &Sdt, from: usize, to: usize) -> Result<Self, SdtError> {
+        let suite = sdt.suite()?;
+        let items = sdt.items();
+        check_position(to, items.len())?;
+        check_position(from, to + 1)?;
+        let roots = items[from + 1..=to]
+            .iter()
+            .map(|item| item.node.gen_proof_with(suite))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            version: sdt.version,
+            from,
+            to,
+            roots,
+        })
+    }
+
+    /// Checks that `new`, the proof at `to`, extends `old`, the proof at `from`
+    pub fn verify(&self, old: &str, new: &str) -> Result<(), SdtError> {
+        let suite = SdtHashSuite::from_version(self.version)?;
+        // the roots describe a chain of `from + roots.len() + 1` items, `to` has to be its end
+        let last = self.from.checked_add(self.roots.len());
+        if last != Some(self.to) {
+            return Err(SdtError::PositionError {
+                position: self.to,
+                len: last.map_or(usize::MAX, |last| last.saturating_add(1)),
+            });
+        }
+        let mut proof = old.to_owned();
+        for root in &self.roots {
+            proof = SdtProof::new()
+                .insert_hex("previous", &proof)
+                .insert_hex("root", root)
+                .digest_with(suite)?;
+        }
+        if proof != new {
+            return Err(SdtError::VerificationError {
+                expected: new.to_owned(),
+                actual: proof,
+            });
+        }
+        Ok(())
+    }
+}
+
+fn check_position(position: usize, len: usize) -> Result<(), SdtError> {
+    if position >= len {
+        return Err(SdtError::PositionError { position, len });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::node::{SdtClaim, SdtNode};
+
+    use super::*;
+
+    fn create_sdt() -> Result<Sdt, SdtError> {
+        let claim: SdtClaim =
+            serde_json::from_str(r#"{"personal": {"name": "Adem", "surname": "Çağlın"}}"#)?;
+        let mutation: SdtClaim = serde_json::from_str(r#"{"personal": {"surname": null}}"#)?;
+        let age: SdtClaim = serde_json::from_str(r#"{"age_over_18": true}"#)?;
+        Ok(Sdt::new("did:p2p:123456", claim.to_node())
+            .mutate(mutation.to_node())
+            .mutate(age.to_node())
+            .mutate(SdtNode::new())
+            .build())
+    }
+
+    #[test]
+    fn consistency_test() -> Result<(), SdtError> {
+        let sdt = create_sdt()?;
+        let history = sdt.history()?;
+        for from in 0..history.len() {
+            for to in from..history.len() {
+                let proof = SdtConsistencyProof::new(&sdt, from, to)?;
+                assert_eq!(proof.roots.len(), to - from);
+                proof.verify(&history[from], &history[to])?;
+            }
+        }
+        let selected = sdt.select("{\n personal {\n name\n }\n}")?;
+        let proof = SdtConsistencyProof::new(&selected, 1, 3)?;
+        assert_eq!(proof, SdtConsistencyProof::new(&sdt, 1, 3)?);
+        let parsed: SdtConsistencyProof = serde_json::from_str(&serde_json::to_string(&proof)?)?;
+        parsed.verify(&history[1], &history[3])?;
+        Ok(())
+    }
+
+    #[test]
+    fn rewritten_history_test() -> Result<(), SdtError> {
+        let sdt = create_sdt()?;
+        let history = sdt.history()?;
+        // same inception, different first mutation
+        let claim: SdtClaim = serde_json::from_str(r#"{"personal": {"name": null}}"#)?;
+        let mut rewritten = sdt.clone();
+        rewritten.inception.next.as_mut().unwrap().node = claim.to_node();
+        let proof = SdtConsistencyProof::new(&rewritten, 0, 3)?;
+        proof.verify(&history[0], &rewritten.gen_proof()?)?;
+        assert!(matches!(
+            proof.verify(&history[0], &history[3]),
+            Err(SdtError::VerificationError { .. })
+        ));
+        let mut proof = SdtConsistencyProof::new(&sdt, 1, 3)?;
+        assert!(proof.verify(&history[0], &history[3]).is_err());
+        proof.to = 2;
+        assert!(matches!(
+            proof.verify(&history[1], &history[3]),
+            Err(SdtError::PositionError { .. })
+        ));
+        let overflowing: SdtConsistencyProof = serde_json::from_str(
+            r#"{"version": 2, "from": 18446744073709551615, "to": 0, "roots": []}"#,
+        )?;
+        assert!(matches!(
+            overflowing.verify(&history[0], &history[0]),
+            Err(SdtError::PositionError { .. })
+        ));
+
+        assert!(matches!(
+            SdtConsistencyProof::new(&sdt, 2, 1),
+            Err(SdtError::PositionError { .. })
+        ));
+        assert!(matches!(
+            SdtConsistencyProof::new(&sdt, 0, 4),
+            Err(SdtError::PositionError {
+                position: 4,
+                len: 4
+            })
+        ));
+        Ok(())
+    }
+}
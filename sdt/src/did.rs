@@ -0,0 +1,391 @@
+//! Subject identifiers, `did:<method>:<id>`.
+//!
+//! `did:key` identifiers carry their Ed25519 key and are resolved locally, other
+//! methods such as `did:p2p` go through a `DidResolver`.
+
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::SdtError,
+    signer::{Ed25519Verifier, SdtVerifier},
+};
+
+/// Multicodec prefix of an Ed25519 public key, `0xed` as a varint
+const ED25519_CODEC: [u8; 2] = [0xed, 0x01];
+pub const ED25519_KEY_TYPE: &str = "Ed25519VerificationKey2020";
+
+#[derive(PartialEq, Eq, Debug, Clone, Hash)]
+pub struct Did {
+    pub method: String,
+    /// Method specific identifier
+    pub id: String,
+}
+
+impl Did {
+    pub fn parse(s: &str) -> Result<Self, SdtError> {
+        let invalid = |reason: &str| SdtError::DidError(format!("{s} {reason}"));
+        let rest = s
+            .strip_prefix("did:")
+            .ok_or_else(|| invalid("should start with did:"))?;
+        let (method, id) = rest
+            .split_once(':')
+            .ok_or_else(|| invalid("has no method"))?;
+        if method.is_empty()
+            || !method
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        {
+            return Err(invalid("has an invalid method"));
+        }
+        let valid_id = !id.is_empty()
+            && !id.ends_with(':')
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || ".-_:%".contains(c));
+        if !valid_id {
+            return Err(invalid("has an invalid identifier"));
+        }
+        Ok(Self {
+            method: method.to_owned(),
+            id: id.to_owned(),
+        })
+    }
+
+    /// A DID with a fragment, e.g. the key id `did:p2p:123456#key-1`
+    pub fn parse_url(s: &str) -> Result<(Self, Option<String>), SdtError> {
+        match s.split_once('#') {
+            Some((did, fragment)) => Ok((Self::parse(did)?, Some(fragment.to_owned()))),
+            None => Ok((Self::parse(s)?, None)),
+        }
+    }
+
+    /// The `did:key` of an Ed25519 public key
+    pub fn from_ed25519(public: &[u8; 32]) -> Self {
+        Self {
+            method: "key".to_owned(),
+            id: ed25519_multibase(public),
+        }
+    }
+}
+
+impl fmt::Display for Did {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "did:{}:{}", self.method, self.id)
+    }
+}
+
+impl FromStr for Did {
+    type Err = SdtError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationMethod {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub key_type: String,
+    pub controller: String,
+    pub public_key_multibase: String,
+}
+
+impl VerificationMethod {
+    /// `fragment` is appended to the controller DID to form the key id
+    pub fn ed25519(controller: &Did, fragment: &str, public: &[u8; 32]) -> Self {
+        Self {
+            id: format!("{controller}#{fragment}"),
+            key_type: ED25519_KEY_TYPE.to_owned(),
+            controller: controller.to_string(),
+            public_key_multibase: ed25519_multibase(public),
+        }
+    }
+
+    pub fn public_key(&self) -> Result<[u8; 32], SdtError> {
+        if self.key_type != ED25519_KEY_TYPE {
+            return Err(SdtError::InvalidKey(format!(
+                "Unsupported key type {}",
+                self.key_type
+            )));
+        }
+        decode_ed25519_multibase(&self.public_key_multibase)
+    }
+}
+
+/// The part of a DID document needed to check signatures
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DidDocument {
+    pub id: String,
+    pub verification_method: Vec<VerificationMethod>,
+}
+
+impl DidDocument {
+    /// The method of a key id, the first method when the key id has no fragment
+    pub fn find_method(&self, key_id: &str) -> Option<&VerificationMethod> {
+        if key_id.contains('#') {
+            self.verification_method.iter().find(|m| m.id == key_id)
+        } else {
+            self.verification_method.first()
+        }
+    }
+}
+
+pub trait DidResolver {
+    fn resolve(&self, did: &Did) -> Result<DidDocument, SdtError>;
+}
+
+/// Derives the document of a `did:key` from the identifier.
+#[derive(Debug, Clone, Default)]
+pub struct DidKeyResolver;
+
+impl DidResolver for DidKeyResolver {
+    fn resolve(&self, did: &Did) -> Result<DidDocument, SdtError> {
+        if did.method != "key" {
+            return Err(SdtError::DidError(format!("{did} is not a did:key")));
+        }
+        let public = decode_ed25519_multibase(&did.id)?;
+        Ok(DidDocument {
+            id: did.to_string(),
+            verification_method: vec![VerificationMethod::ed25519(did, &did.id, &public)],
+        })
+    }
+}
+
+/// Documents registered in memory, a stand-in for a `did:p2p` network.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryDidResolver {
+    documents: HashMap<String, DidDocument>,
+}
+
+impl MemoryDidResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, document: DidDocument) -> &mut Self {
+        self.documents.insert(document.id.to_owned(), document);
+        self
+    }
+}
+
+impl DidResolver for MemoryDidResolver {
+    fn resolve(&self, did: &Did) -> Result<DidDocument, SdtError> {
+        self.documents
+            .get(&did.to_string())
+            .cloned()
+            .ok_or_else(|| SdtError::DidError(format!("{did} can't be resolved")))
+    }
+}
+
+/// Picks the resolver of the DID method, `did:key` is always resolved locally.
+pub struct MethodResolver {
+    methods: HashMap<String, Box<dyn DidResolver>>,
+}
+
+impl MethodResolver {
+    pub fn new() -> Self {
+        let mut methods: HashMap<String, Box<dyn DidResolver>> = HashMap::new();
+        methods.insert("key".to_owned(), Box::new(DidKeyResolver));
+        Self { methods }
+    }
+
+    pub fn with_method(mut self, method: &str, resolver: Box<dyn DidResolver>) -> Self {
+        self.methods.insert(method.to_owned(), resolver);
+        self
+    }
+}
+
+impl Default for MethodResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DidResolver for MethodResolver {
+    fn resolve(&self, did: &Did) -> Result<DidDocument, SdtError> {
+        self.methods
+            .get(&did.method)
+            .ok_or_else(|| SdtError::DidError(format!("did:{} is not supported", did.method)))?
+            .resolve(did)
+    }
+}
+
+/// Checks signatures with the key its key id resolves to, e.g. `did:p2p:123456#key-1`.
+pub struct DidVerifier<'a> {
+    resolver: &'a dyn DidResolver,
+    controller: Option<Did>,
+}
+
+impl<'a> DidVerifier<'a> {
+    pub fn new(resolver: &'a dyn DidResolver) -> Self {
+        Self {
+            resolver,
+            controller: None,
+        }
+    }
+
+    /// Only accepts keys of the DID, e.g. the holder a presentation is bound to
+    pub fn with_controller(mut self, controller: &str) -> Result<Self, SdtError> {
+        self.controller = Some(Did::parse(controller)?);
+        Ok(self)
+    }
+}
+
+impl SdtVerifier for DidVerifier<'_> {
+    fn verify(
+        &self,
+        algorithm: &str,
+        key_id: Option<&str>,
+        msg: &[u8],
+        sig: &[u8],
+    ) -> Result<(), SdtError> {
+        let key_id = key_id.ok_or_else(|| SdtError::DidError("Key id is missing".to_owned()))?;
+        let (did, _) = Did::parse_url(key_id)?;
+        if let Some(controller) = &self.controller {
+            if &did != controller {
+                return Err(SdtError::DidError(format!(
+                    "{key_id} is not a key of {controller}"
+                )));
+            }
+        }
+        let document = self.resolver.resolve(&did)?;
+        let method = document
+            .find_method(key_id)
+            .ok_or_else(|| SdtError::DidError(format!("{key_id} is not in the document")))?;
+        Ed25519Verifier::from_public(&method.public_key()?)?.verify(
+            algorithm,
+            Some(key_id),
+            msg,
+            sig,
+        )
+    }
+}
+
+/// `z` and base58btc of the multicodec prefixed key
+pub(crate) fn ed25519_multibase(public: &[u8; 32]) -> String {
+    let mut bytes = ED25519_CODEC.to_vec();
+    bytes.extend_from_slice(public);
+    format!("z{}", bs58::encode(bytes).into_string())
+}
+
+pub(crate) fn decode_ed25519_multibase(s: &str) -> Result<[u8; 32], SdtError> {
+    let invalid = || SdtError::InvalidKey(format!("{s} is not a multibase Ed25519 key"));
+    let encoded = s.strip_prefix('z').ok_or_else(invalid)?;
+    let bytes = bs58::decode(encoded).into_vec().map_err(|_| invalid())?;
+    bytes
+        .strip_prefix(&ED25519_CODEC)
+        .and_then(|key| key.try_into().ok())
+        .ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::signer::{Ed25519Signer, SdtSigner, ED25519_ALGORITHM};
+
+    use super::*;
+
+    #[test]
+    fn parse_test() -> Result<(), SdtError> {
+        let did = Did::parse("did:p2p:123456")?;
+        assert_eq!((did.method.as_str(), did.id.as_str()), ("p2p", "123456"));
+        assert_eq!(did.to_string(), "did:p2p:123456");
+        let (did, fragment) = Did::parse_url("did:web:example.com:user%20a#key-1")?;
+        assert_eq!(did.id, "example.com:user%20a");
+        assert_eq!(fragment.as_deref(), Some("key-1"));
+        for invalid in [
+            "p2p:123456",
+            "did:p2p",
+            "did:P2P:1",
+            "did::1",
+            "did:p2p:",
+            "did:p2p:a:",
+            "did:p2p:a b",
+        ] {
+            assert!(
+                matches!(Did::parse(invalid), Err(SdtError::DidError(_))),
+                "{invalid} should be invalid"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn did_key_test() -> Result<(), SdtError> {
+        let signer = Ed25519Signer::new();
+        let did = Did::from_ed25519(&signer.public_key());
+        assert!(did.to_string().starts_with("did:key:z6Mk"));
+        let document = DidKeyResolver.resolve(&Did::parse(&did.to_string())?)?;
+        let method = &document.verification_method[0];
+        assert_eq!(method.id, format!("{did}#{}", did.id));
+        assert_eq!(method.public_key()?, signer.public_key());
+
+        let signer = signer.with_key_id(&method.id);
+        let sig = signer.sign(b"message")?;
+        let resolver = MethodResolver::new();
+        let verifier = DidVerifier::new(&resolver);
+        verifier.verify(
+            ED25519_ALGORITHM,
+            signer.key_id().as_deref(),
+            b"message",
+            &sig,
+        )?;
+        assert!(verifier
+            .verify(
+                ED25519_ALGORITHM,
+                signer.key_id().as_deref(),
+                b"other",
+                &sig
+            )
+            .is_err());
+        assert!(DidKeyResolver
+            .resolve(&Did::parse("did:key:z6Mk123")?)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn p2p_resolver_test() -> Result<(), SdtError> {
+        let signer = Ed25519Signer::new().with_key_id("did:p2p:123456#key-1");
+        let did = Did::parse("did:p2p:123456")?;
+        let mut p2p = MemoryDidResolver::new();
+        p2p.insert(DidDocument {
+            id: did.to_string(),
+            verification_method: vec![VerificationMethod::ed25519(
+                &did,
+                "key-1",
+                &signer.public_key(),
+            )],
+        });
+        let resolver = MethodResolver::new().with_method("p2p", Box::new(p2p));
+        let sig = signer.sign(b"message")?;
+        let key_id = signer.key_id();
+        DidVerifier::new(&resolver)
+            .with_controller("did:p2p:123456")?
+            .verify(ED25519_ALGORITHM, key_id.as_deref(), b"message", &sig)?;
+        // holder binding rejects keys of other DIDs
+        assert!(matches!(
+            DidVerifier::new(&resolver)
+                .with_controller("did:p2p:654321")?
+                .verify(ED25519_ALGORITHM, key_id.as_deref(), b"message", &sig),
+            Err(SdtError::DidError(_))
+        ));
+        assert!(DidVerifier::new(&resolver)
+            .verify(
+                ED25519_ALGORITHM,
+                Some("did:p2p:123456#key-2"),
+                b"message",
+                &sig
+            )
+            .is_err());
+        assert!(resolver.resolve(&Did::parse("did:p2p:654321")?).is_err());
+        assert!(resolver
+            .resolve(&Did::parse("did:web:example.com")?)
+            .is_err());
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,10 +7,326 @@ pub enum SdtError {
     StdError(#[from] std::io::Error),
     #[error(transparent)]
     SerdeJsonError(#[from] serde_json::Error),
+    #[error(transparent)]
+    HexError(#[from] hex::FromHexError),
+    #[error("Invalid cbor: {0}")]
+    CborError(String),
     #[error(
         "Proof doesn't match the computed proof. Proof is {expected}, computed proof is {actual}."
     )]
     VerificationError { expected: String, actual: String },
+    #[error("Unsupported sdt version {0}")]
+    UnsupportedVersion(u64),
+    #[error("Invalid key: {0}")]
+    InvalidKey(String),
+    #[error("Invalid signature")]
+    InvalidSignature,
+    #[error("Invalid sd-jwt: {0}")]
+    SdJwtError(String),
+    #[error("Invalid credential: {0}")]
+    CredentialError(String),
+    #[error("Invalid compact presentation: {0}")]
+    CompactError(String),
+    /// `path` is the query path of the enclosing block, `line` and `column` start at 1
+    #[error("Invalid query at line {line}, column {column}: {message}")]
+    QueryError {
+        message: String,
+        path: String,
+        line: usize,
+        column: usize,
+    },
+    #[error("Unknown handle {0}")]
+    UnknownHandle(String),
+    #[error("Session is full, it holds at most {0} tries")]
+    SessionFull(usize),
+    #[error("Handles need a session")]
+    SessionRequired,
+    /// Positions start at 0 for the inception
+    #[error("Position {position} is out of range, the chain has {len} items")]
+    PositionError { position: usize, len: usize },
+    /// A mutation without a signature of the keys committed before it
+    #[error("Mutation {position} is not authorized: {message}")]
+    AuthorizationError { position: usize, message: String },
+    /// Too few receipts or a witness that refuses a head
+    #[error("Not witnessed: {0}")]
+    WitnessError(String),
+    /// A malformed status list or a status entry that can't be read
+    #[error("Invalid status: {0}")]
+    StatusError(String),
+    /// The status bit of the trie is set, `purpose` tells if it's revoked or suspended
+    #[error("Status of the trie is set in {list} at {index} for {purpose}")]
+    Revoked {
+        list: String,
+        index: usize,
+        purpose: String,
+    },
+    /// A malformed DID or one that can't be resolved
+    #[error("Invalid did: {0}")]
+    DidError(String),
+    /// Tries that don't share a subject, version or inception
+    #[error("Invalid fork: {0}")]
+    ForkError(String),
+    #[error("Invalid pipeline: {0}")]
+    PipelineError(String),
+    /// A tampered anchor log or a root that isn't anchored
+    #[error("Invalid anchor: {0}")]
+    AnchorError(String),
+    #[error("Unknown subject {0}")]
+    UnknownSubject(String),
+    /// A store whose contents can't be read back, e.g. a foreign file in its directory
+    #[error("Invalid store: {0}")]
+    StoreError(String),
+    /// `actual` is the stored head, `expected` the head the write was based on
+    #[error("Conflicting write to {subject}, head is {actual}, expected {expected}")]
+    StoreConflict {
+        subject: String,
+        expected: String,
+        actual: String,
+    },
     #[error("{0}")]
     Other(String),
 }
+
+/// Broad class of an error, e.g. to pick a status code
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SdtErrorCategory {
+    /// Malformed JSON, hex, CBOR, keys or presentations
+    Input,
+    /// A proof, signature or anchor doesn't match
+    Verification,
+    /// A selection query can't be parsed
+    Query,
+    /// Unknown handles, a full or missing session
+    Session,
+    /// Unknown subjects or writes based on an outdated head
+    Store,
+    Unsupported,
+    Internal,
+}
+
+impl SdtErrorCategory {
+    /// Serialized name of the category, e.g. `verification`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SdtErrorCategory::Input => "input",
+            SdtErrorCategory::Verification => "verification",
+            SdtErrorCategory::Query => "query",
+            SdtErrorCategory::Session => "session",
+            SdtErrorCategory::Store => "store",
+            SdtErrorCategory::Unsupported => "unsupported",
+            SdtErrorCategory::Internal => "internal",
+        }
+    }
+}
+
+/// Structured fields of an error, absent fields don't apply to the error
+#[derive(PartialEq, Eq, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SdtErrorDetails {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actual: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+}
+
+impl SdtErrorDetails {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+impl SdtError {
+    /// Stable identifier of the variant, e.g. `verification_failed`
+    pub fn code(&self) -> &'static str {
+        match self {
+            SdtError::StdError(_) => "io_error",
+            SdtError::SerdeJsonError(_) => "invalid_json",
+            SdtError::HexError(_) => "invalid_hex",
+            SdtError::CborError(_) => "invalid_cbor",
+            SdtError::VerificationError { .. } => "verification_failed",
+            SdtError::UnsupportedVersion(_) => "unsupported_version",
+            SdtError::InvalidKey(_) => "invalid_key",
+            SdtError::InvalidSignature => "invalid_signature",
+            SdtError::SdJwtError(_) => "invalid_sd_jwt",
+            SdtError::CredentialError(_) => "invalid_credential",
+            SdtError::CompactError(_) => "invalid_compact",
+            SdtError::QueryError { .. } => "invalid_query",
+            SdtError::UnknownHandle(_) => "unknown_handle",
+            SdtError::SessionFull(_) => "session_full",
+            SdtError::SessionRequired => "session_required",
+            SdtError::PositionError { .. } => "invalid_position",
+            SdtError::AuthorizationError { .. } => "unauthorized_mutation",
+            SdtError::WitnessError(_) => "witness_failed",
+            SdtError::StatusError(_) => "invalid_status",
+            SdtError::Revoked { .. } => "revoked",
+            SdtError::DidError(_) => "invalid_did",
+            SdtError::ForkError(_) => "invalid_fork",
+            SdtError::PipelineError(_) => "invalid_pipeline",
+            SdtError::AnchorError(_) => "invalid_anchor",
+            SdtError::UnknownSubject(_) => "unknown_subject",
+            SdtError::StoreConflict { .. } => "store_conflict",
+            SdtError::StoreError(_) => "invalid_store",
+            SdtError::Other(_) => "other",
+        }
+    }
+
+    pub fn category(&self) -> SdtErrorCategory {
+        match self {
+            SdtError::SerdeJsonError(_)
+            | SdtError::HexError(_)
+            | SdtError::CborError(_)
+            | SdtError::InvalidKey(_)
+            | SdtError::SdJwtError(_)
+            | SdtError::CredentialError(_)
+            | SdtError::CompactError(_)
+            | SdtError::PositionError { .. }
+            | SdtError::ForkError(_)
+            | SdtError::DidError(_)
+            | SdtError::StatusError(_)
+            | SdtError::PipelineError(_) => SdtErrorCategory::Input,
+            SdtError::VerificationError { .. }
+            | SdtError::InvalidSignature
+            | SdtError::AuthorizationError { .. }
+            | SdtError::WitnessError(_)
+            | SdtError::Revoked { .. }
+            | SdtError::AnchorError(_) => SdtErrorCategory::Verification,
+            SdtError::QueryError { .. } => SdtErrorCategory::Query,
+            SdtError::UnknownHandle(_) | SdtError::SessionFull(_) | SdtError::SessionRequired => {
+                SdtErrorCategory::Session
+            }
+            SdtError::UnknownSubject(_)
+            | SdtError::StoreConflict { .. }
+            | SdtError::StoreError(_) => SdtErrorCategory::Store,
+            SdtError::UnsupportedVersion(_) => SdtErrorCategory::Unsupported,
+            SdtError::StdError(_) | SdtError::Other(_) => SdtErrorCategory::Internal,
+        }
+    }
+
+    pub fn details(&self) -> SdtErrorDetails {
+        match self {
+            // errors that don't come from parsing text have no position
+            SdtError::SerdeJsonError(err) if err.line() > 0 => SdtErrorDetails {
+                line: Some(err.line()),
+                column: Some(err.column()),
+                ..Default::default()
+            },
+            SdtError::VerificationError { expected, actual }
+            | SdtError::StoreConflict {
+                expected, actual, ..
+            } => SdtErrorDetails {
+                expected: Some(expected.to_owned()),
+                actual: Some(actual.to_owned()),
+                ..Default::default()
+            },
+            SdtError::QueryError {
+                path, line, column, ..
+            } => SdtErrorDetails {
+                path: Some(path.to_owned()),
+                line: Some(*line),
+                column: Some(*column),
+                ..Default::default()
+            },
+            _ => SdtErrorDetails::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_category_test() {
+        let cases = vec![
+            (
+                SdtError::from(serde_json::from_str::<u8>("x").unwrap_err()),
+                "invalid_json",
+                SdtErrorCategory::Input,
+            ),
+            (
+                SdtError::VerificationError {
+                    expected: "0x12".to_owned(),
+                    actual: "0x34".to_owned(),
+                },
+                "verification_failed",
+                SdtErrorCategory::Verification,
+            ),
+            (
+                SdtError::Revoked {
+                    list: "list.json".to_owned(),
+                    index: 3,
+                    purpose: "revocation".to_owned(),
+                },
+                "revoked",
+                SdtErrorCategory::Verification,
+            ),
+            (
+                SdtError::QueryError {
+                    message: "Unmatched `}`".to_owned(),
+                    path: String::new(),
+                    line: 4,
+                    column: 1,
+                },
+                "invalid_query",
+                SdtErrorCategory::Query,
+            ),
+            (
+                SdtError::UnknownHandle("0x12".to_owned()),
+                "unknown_handle",
+                SdtErrorCategory::Session,
+            ),
+            (
+                SdtError::SessionFull(1),
+                "session_full",
+                SdtErrorCategory::Session,
+            ),
+            (
+                SdtError::UnknownSubject("did:p2p:123456".to_owned()),
+                "unknown_subject",
+                SdtErrorCategory::Store,
+            ),
+            (
+                SdtError::StoreError("invalid file".to_owned()),
+                "invalid_store",
+                SdtErrorCategory::Store,
+            ),
+            (
+                SdtError::UnsupportedVersion(9),
+                "unsupported_version",
+                SdtErrorCategory::Unsupported,
+            ),
+            (
+                SdtError::from(std::io::Error::other("disk")),
+                "io_error",
+                SdtErrorCategory::Internal,
+            ),
+            (
+                SdtError::Other("other".to_owned()),
+                "other",
+                SdtErrorCategory::Internal,
+            ),
+        ];
+        for (err, code, category) in cases {
+            assert_eq!(err.code(), code);
+            assert_eq!(err.category(), category);
+            assert_eq!(serde_json::to_value(category).unwrap(), category.as_str());
+        }
+    }
+
+    #[test]
+    fn details_test() {
+        let err = SdtError::VerificationError {
+            expected: "0x12".to_owned(),
+            actual: "0x34".to_owned(),
+        };
+        assert_eq!(err.details().expected.as_deref(), Some("0x12"));
+        assert_eq!(err.details().actual.as_deref(), Some("0x34"));
+        assert!(SdtError::InvalidSignature.details().is_empty());
+    }
+}
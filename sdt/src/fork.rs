@@ -0,0 +1,217 @@
+//! Divergent copies of a trie, e.g. when two devices mutate the same head.
+
+use crate::{error::SdtError, node::SdtNode, signer::SdtSigner, Sdt};
+
+/// Where two chains of the same subject diverge
+#[derive(PartialEq, Debug, Clone)]
+pub struct SdtFork {
+    /// Position of the last item both chains share, 0 is the inception
+    pub base: usize,
+    /// Proof of both chains at `base`
+    pub base_proof: String,
+    /// Nodes of our items after `base`
+    pub ours: Vec<SdtNode>,
+    /// Nodes of their items after `base`
+    pub theirs: Vec<SdtNode>,
+}
+
+impl SdtFork {
+    /// Compares the chains by proof, they need the same subject, version and inception.
+    pub fn new(ours: &Sdt, theirs: &Sdt) -> Result<Self, SdtError> {
+        if ours.subject != theirs.subject {
+            return Err(SdtError::ForkError(format!(
+                "{} and {} are different tries",
+                ours.subject, theirs.subject
+            )));
+        }
+        if ours.version != theirs.version {
+            return Err(SdtError::ForkError(format!(
+                "Versions of {} differ, {} and {}",
+                ours.subject, ours.version, theirs.version
+            )));
+        }
+        let our_history = ours.history()?;
+        let their_history = theirs.history()?;
+        let shared = our_history
+            .iter()
+            .zip(&their_history)
+            .take_while(|(a, b)| a == b)
+            .count();
+        if shared == 0 {
+            return Err(SdtError::ForkError(format!(
+                "Inceptions of {} differ",
+                ours.subject
+            )));
+        }
+        let after = |sdt: &Sdt| -> Vec<SdtNode> {
+            sdt.items()[shared..]
+                .iter()
+                .map(|item| item.node.clone())
+                .collect()
+        };
+        Ok(Self {
+            base: shared - 1,
+            base_proof: our_history[shared - 1].to_owned(),
+            ours: after(ours),
+            theirs: after(theirs),
+        })
+    }
+
+    /// Both chains have items the other one doesn't, one chain extending the other is
+    /// not a fork
+    pub fn is_forked(&self) -> bool {
+        !self.ours.is_empty() && !self.theirs.is_empty()
+    }
+}
+
+/// Our chain followed by their mutations after the fork point, so their mutations win
+/// where both changed the same claim. A chain extending the other one is returned as is.
+///
+/// Mutations keep their salts when they are re-applied, so their nodes already in our
+/// chain, e.g. from an earlier merge, are skipped. Re-applied mutations get new chain
+/// proofs, so they are signed again by `signers` and their receipts are left out. The
+/// result has to verify, tries with keys fail with `AuthorizationError` when a mutation
+/// isn't authorized.
+pub fn merge(ours: &Sdt, theirs: &Sdt, signers: &[&dyn SdtSigner]) -> Result<Sdt, SdtError> {
+    let fork = SdtFork::new(ours, theirs)?;
+    let merged = if fork.ours.is_empty() {
+        theirs.clone()
+    } else {
+        let mut merged = ours.clone();
+        for node in fork.theirs {
+            if !fork.ours.contains(&node) {
+                merged.mutate_signed(node, signers)?;
+            }
+        }
+        merged
+    };
+    merged.verify(&merged.gen_proof()?)?;
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        auth::{ed25519_key, ASSERTION_METHOD, KEYS},
+        node::SdtClaim,
+        proof::SdtHashSuite,
+        signer::Ed25519Signer,
+    };
+
+    use super::*;
+
+    fn claim(json: &str) -> Result<SdtNode, SdtError> {
+        let claim: SdtClaim = serde_json::from_str(json)?;
+        Ok(claim.to_node())
+    }
+
+    fn create_sdt() -> Result<Sdt, SdtError> {
+        Ok(Sdt::new(
+            "did:p2p:123456",
+            claim(r#"{"personal": {"name": "Adem", "surname": "Çağlın"}}"#)?,
+        )
+        .mutate(claim(r#"{"personal": {"surname": null}}"#)?)
+        .build())
+    }
+
+    #[test]
+    fn fork_test() -> Result<(), SdtError> {
+        let base = create_sdt()?;
+        let phone = base
+            .clone()
+            .mutate(claim(r#"{"personal": {"name": "Ali"}}"#)?)
+            .build();
+        let laptop = base
+            .clone()
+            .mutate(claim(r#"{"age_over_18": true}"#)?)
+            .mutate(claim(r#"{"personal": {"name": "Veli"}}"#)?)
+            .build();
+        let fork = SdtFork::new(&phone, &laptop)?;
+        assert!(fork.is_forked());
+        assert_eq!(fork.base, 1);
+        assert_eq!(fork.base_proof, base.gen_proof()?);
+        assert_eq!((fork.ours.len(), fork.theirs.len()), (1, 2));
+
+        let merged = merge(&phone, &laptop, &[])?;
+        assert_eq!(merged.items().len(), 5);
+        assert!(merged.verify_at(2, &phone.gen_proof()?)?);
+        let claims = merged.current_node().to_claim();
+        let expected: SdtClaim = serde_json::from_str(
+            r#"{"personal": {"name": "Veli", "surname": null}, "age_over_18": true}"#,
+        )?;
+        assert_eq!(claims, expected);
+        // their mutations are already part of the merge
+        assert_eq!(merge(&merged, &laptop, &[])?, merged);
+        assert_eq!(merge(&merged, &phone, &[])?, merged);
+        Ok(())
+    }
+
+    #[test]
+    fn fast_forward_test() -> Result<(), SdtError> {
+        let base = create_sdt()?;
+        let ahead = base.clone().mutate(SdtNode::new()).build();
+        let fork = SdtFork::new(&base, &ahead)?;
+        assert!(!fork.is_forked());
+        assert_eq!(merge(&base, &ahead, &[])?, ahead);
+        assert_eq!(merge(&ahead, &base, &[])?, ahead);
+        assert_eq!(merge(&base, &base, &[])?, base);
+
+        let other = Sdt::new("did:p2p:123456", claim(r#"{"name": "Adem"}"#)?);
+        assert!(matches!(
+            SdtFork::new(&base, &other),
+            Err(SdtError::ForkError(_))
+        ));
+        let mut other = base.clone();
+        other.with_suite(SdtHashSuite::CborSha256);
+        assert!(matches!(
+            SdtFork::new(&base, &other),
+            Err(SdtError::ForkError(ref message)) if message.contains("Versions")
+        ));
+        let other = Sdt::new("did:p2p:654321", base.inception.node.clone());
+        assert!(matches!(
+            merge(&base, &other, &[]),
+            Err(SdtError::ForkError(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn keyed_merge_test() -> Result<(), SdtError> {
+        let signer = Ed25519Signer::new().with_key_id("key_1");
+        let key = SdtNode::new()
+            .add_str_value("key_1", &ed25519_key(&signer.public_key()))
+            .build();
+        let keys = SdtNode::new().add_node(ASSERTION_METHOD, key).build();
+        let base = Sdt::new(
+            "did:p2p:123456",
+            SdtNode::new().add_node(KEYS, keys).build(),
+        );
+        let mut phone = base.clone();
+        phone.mutate_signed(claim(r#"{"name": "Ali"}"#)?, &[&signer])?;
+        let mut laptop = base.clone();
+        laptop.mutate_signed(claim(r#"{"age_over_18": true}"#)?, &[&signer])?;
+
+        let merged = merge(&phone, &laptop, &[&signer])?;
+        assert_eq!(merged.items().len(), 3);
+        assert!(merged.verify_authorized(&merged.gen_proof()?)?);
+        assert!(matches!(
+            merge(&phone, &laptop, &[]),
+            Err(SdtError::AuthorizationError { position: 2, .. })
+        ));
+
+        // a chain ahead of ours is returned only when it verifies
+        let ahead = phone.clone().mutate(SdtNode::new()).build();
+        assert!(matches!(
+            merge(&phone, &ahead, &[&signer]),
+            Err(SdtError::AuthorizationError { position: 2, .. })
+        ));
+        assert!(matches!(
+            merge(&ahead, &phone, &[&signer]),
+            Err(SdtError::AuthorizationError { position: 2, .. })
+        ));
+        let mut signed = phone.clone();
+        signed.mutate_signed(SdtNode::new(), &[&signer])?;
+        assert_eq!(merge(&phone, &signed, &[])?, signed);
+        Ok(())
+    }
+}
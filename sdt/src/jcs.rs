@@ -0,0 +1,111 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::SdtError;
+
+/// Serializes the payload with the JSON Canonicalization Scheme (RFC 8785).
+///
+/// Object members are sorted by their UTF-16 code units, strings use the minimal
+/// escaping of ECMAScript `JSON.stringify` and numbers are written as IEEE 754
+/// doubles in ECMAScript `Number.prototype.toString` form (`1.0` becomes `1`).
+pub fn to_jcs_string<T: Serialize>(payload: &T) -> Result<String, SdtError> {
+    let mut out = String::new();
+    write_value(&serde_json::to_value(payload)?, &mut out);
+    Ok(out)
+}
+
+fn write_value(value: &Value, out: &mut String) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => {
+            // serde_json numbers are always finite, so this never yields NaN or Infinity
+            let f = n.as_f64().unwrap_or_default();
+            out.push_str(ryu_js::Buffer::new().format(f));
+        }
+        Value::String(s) => write_str(s, out),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(item, out);
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.encode_utf16().cmp(b.0.encode_utf16()));
+            out.push('{');
+            for (i, (k, v)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_str(k, out);
+                out.push(':');
+                write_value(v, out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_str(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{08}' => out.push_str("\\b"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\u{0c}' => out.push_str("\\f"),
+            '\r' => out.push_str("\\r"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jcs_numbers_test() -> Result<(), SdtError> {
+        let value: Value = serde_json::from_str(
+            "[1.0, -0.0, 0.1, 1e21, 1e-7, 123456789012, 9007199254740993, 4.5e-324]",
+        )?;
+        assert_eq!(
+            to_jcs_string(&value)?,
+            "[1,0,0.1,1e+21,1e-7,123456789012,9007199254740992,5e-324]"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn jcs_strings_test() -> Result<(), SdtError> {
+        let value: Value =
+            serde_json::from_str(r#"["Çağlın", "\u0001\u001f\b\t\n\f\r\"\\/", "\u007f€😀"]"#)?;
+        assert_eq!(
+            to_jcs_string(&value)?,
+            "[\"Çağlın\",\"\\u0001\\u001f\\b\\t\\n\\f\\r\\\"\\\\/\",\"\u{7f}€😀\"]"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn jcs_key_order_test() -> Result<(), SdtError> {
+        // U+1F600 sorts before U+FB33 by UTF-16 code units but after it by code points
+        let value: Value = serde_json::from_str(
+            r#"{"\ufb33": 1, "😀": 2, "b": {"z": null, "a": true}, "a": []}"#,
+        )?;
+        assert_eq!(
+            to_jcs_string(&value)?,
+            "{\"a\":[],\"b\":{\"a\":true,\"z\":null},\"😀\":2,\"\u{fb33}\":1}"
+        );
+        Ok(())
+    }
+}
@@ -1,26 +1,51 @@
+pub mod anchor;
+pub mod auth;
+pub mod cbor;
+pub mod compact;
+pub mod consistency;
+pub mod did;
 pub mod error;
+pub mod fork;
+pub mod jcs;
+pub mod merkle;
 pub mod node;
 pub mod proof;
+pub mod sdjwt;
 pub mod service;
+pub mod signer;
+pub mod status;
+pub mod store;
 pub mod utils;
 pub mod value;
+pub mod vc;
+pub mod witness;
 
-use proof::SdtProof;
+use auth::SdtSignature;
+use cbor::Cbor;
 use error::SdtError;
 use node::SdtNode;
+use proof::{SdtHashSuite, SdtProof};
 use serde::{Deserialize, Serialize};
+use signer::SdtSigner;
+use witness::SdtReceipt;
 
-const VERSION: u64 = 0x1;
+const VERSION: u64 = SdtHashSuite::JcsSha256 as u64;
 
 #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub struct SdtItem {
     pub node: SdtNode,
     pub next: Option<Box<SdtItem>>,
+    /// Signatures of the chain proof after this item, they aren't part of any proof
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signatures: Vec<SdtSignature>,
+    /// Receipts of witnesses over the chain proof after this item, see `witness`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub receipts: Vec<SdtReceipt>,
 }
 
 #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub struct Sdt {
-    pub version: u64, // JSON + HEX + SHA256
+    pub version: u64, // See SdtHashSuite
     pub subject: String,
     pub inception: SdtItem,
 }
@@ -33,26 +58,82 @@ impl SdtItem {
         self.next.as_mut().unwrap().find_current()
     }
 
+    /// serde_json suite like `gen_proof`, `Sdt::select` uses the suite of the trie version
     pub fn select(&mut self, query: &str) -> Result<&mut Self, SdtError> {
-        self.node.select(query)?;
+        self.select_with(query, SdtHashSuite::JsonSha256)
+    }
+
+    pub fn select_with(&mut self, query: &str, suite: SdtHashSuite) -> Result<&mut Self, SdtError> {
+        self.node.select_with(query, suite)?;
         if self.next.is_none() {
             return Ok(self);
         }
-        self.next.as_mut().unwrap().select(query)
+        self.next.as_mut().unwrap().select_with(query, suite)
     }
 
+    /// serde_json suite, `Sdt::gen_proof` hashes with the suite of the trie version
     pub fn gen_proof(&self, prev: &str) -> Result<String, SdtError> {
-        let node_proof = self.node.gen_proof()?;
+        self.gen_proof_with(prev, SdtHashSuite::JsonSha256)
+    }
+
+    pub fn gen_proof_with(&self, prev: &str, suite: SdtHashSuite) -> Result<String, SdtError> {
+        let node_proof = self.node.gen_proof_with(suite)?;
         let item_proof = SdtProof::new()
-            .insert_str("previous", prev)
-            .insert_str("root", &node_proof)
-            .digest()?;
+            .insert_hex("previous", prev)
+            .insert_hex("root", &node_proof)
+            .digest_with(suite)?;
         if let Some(next) = &self.next {
-            return next.gen_proof(&item_proof);
+            next.gen_proof_with(&item_proof, suite)
         } else {
-            return Ok(item_proof);
+            Ok(item_proof)
         }
     }
+
+    pub fn to_cbor(&self) -> Result<Cbor, SdtError> {
+        let mut entries = vec![(Cbor::text("node"), self.node.to_cbor()?)];
+        if let Some(next) = &self.next {
+            entries.push((Cbor::text("next"), next.to_cbor()?));
+        }
+        for (key, signatures) in [
+            ("signatures", &self.signatures),
+            ("receipts", &self.receipts),
+        ] {
+            if !signatures.is_empty() {
+                let signatures = signatures
+                    .iter()
+                    .map(SdtSignature::to_cbor)
+                    .collect::<Result<Vec<Cbor>, SdtError>>()?;
+                entries.push((Cbor::text(key), Cbor::Array(signatures)));
+            }
+        }
+        Ok(Cbor::Map(entries))
+    }
+
+    pub fn from_cbor(item: &Cbor) -> Result<Self, SdtError> {
+        let node = item
+            .get("node")
+            .ok_or_else(|| SdtError::CborError("Item should have a node".to_owned()))?;
+        let next = match item.get("next") {
+            Some(next) => Some(Box::new(SdtItem::from_cbor(next)?)),
+            None => None,
+        };
+        Ok(SdtItem {
+            node: SdtNode::from_cbor(node)?,
+            next,
+            signatures: signatures_from_cbor(item.get("signatures"))?,
+            receipts: signatures_from_cbor(item.get("receipts"))?,
+        })
+    }
+}
+
+fn signatures_from_cbor(item: Option<&Cbor>) -> Result<Vec<SdtSignature>, SdtError> {
+    match item {
+        Some(Cbor::Array(signatures)) => signatures.iter().map(SdtSignature::from_cbor).collect(),
+        Some(_) => Err(SdtError::CborError(
+            "Signatures should be an array".to_owned(),
+        )),
+        None => Ok(vec![]),
+    }
 }
 
 impl Sdt {
@@ -60,40 +141,195 @@ impl Sdt {
         Sdt {
             version: VERSION,
             subject: sub.to_owned(),
-            inception: SdtItem { node, next: None },
+            inception: SdtItem {
+                node,
+                next: None,
+                signatures: vec![],
+                receipts: vec![],
+            },
         }
     }
 
+    pub fn with_suite(&mut self, suite: SdtHashSuite) -> &mut Self {
+        self.version = suite.version();
+        self
+    }
+
+    pub fn suite(&self) -> Result<SdtHashSuite, SdtError> {
+        SdtHashSuite::from_version(self.version)
+    }
+
     pub fn mutate(&mut self, node: SdtNode) -> &mut Self {
         let current = self.inception.find_current();
-        current.next = Some(Box::new(SdtItem { node, next: None }));
+        current.next = Some(Box::new(SdtItem {
+            node,
+            next: None,
+            signatures: vec![],
+            receipts: vec![],
+        }));
         self
     }
 
+    /// Appends a mutation signed by keys of `keys/assertion_method`, see `auth`
+    pub fn mutate_signed(
+        &mut self,
+        node: SdtNode,
+        signers: &[&dyn SdtSigner],
+    ) -> Result<&mut Self, SdtError> {
+        let mut item = SdtItem {
+            node,
+            next: None,
+            signatures: vec![],
+            receipts: vec![],
+        };
+        let proof = item.gen_proof_with(&self.gen_proof()?, self.suite()?)?;
+        item.signatures = auth::sign(&proof, signers)?;
+        self.inception.find_current().next = Some(Box::new(item));
+        Ok(self)
+    }
+
     pub fn build(&mut self) -> Self {
         self.to_owned()
     }
 
     pub fn select(&self, query: &str) -> Result<Sdt, SdtError> {
+        let suite = self.suite()?;
         let mut sdt = self.clone();
-        sdt.inception.select(query)?;
+        sdt.inception.select_with(query, suite)?;
         Ok(sdt)
     }
 
     pub fn gen_proof(&self) -> Result<String, SdtError> {
-        let inception_root = self.inception.node.gen_proof()?;
-        let inception_proof = SdtProof::new()
-            .insert_i64("version", VERSION as i64)
-            .insert_str("subject", &self.subject)
-            .insert_str("root", &inception_root)
-            .digest()?;
+        let suite = self.suite()?;
+        let inception_proof = self.inception_proof(suite)?;
         if let Some(next) = &self.inception.next {
-            return next.gen_proof(&inception_proof);
+            return next.gen_proof_with(&inception_proof, suite);
+        }
+        Ok(inception_proof)
+    }
+
+    /// Proof of the chain as it was after the item at `position`, the inception is at 0
+    pub fn gen_proof_at(&self, position: usize) -> Result<String, SdtError> {
+        self.truncate(position)?.gen_proof()
+    }
+
+    /// Verifies the chain up to the item at `position` against a historical proof
+    pub fn verify_at(&self, position: usize, proof: &str) -> Result<bool, SdtError> {
+        self.truncate(position)?.verify(proof)
+    }
+
+    /// The trie as it was after the item at `position`, later items are left out
+    pub fn truncate(&self, position: usize) -> Result<Sdt, SdtError> {
+        let len = self.items().len();
+        if position >= len {
+            return Err(SdtError::PositionError { position, len });
+        }
+        let mut sdt = self.clone();
+        let mut item = &mut sdt.inception;
+        for _ in 0..position {
+            item = item.next.as_mut().unwrap();
+        }
+        item.next = None;
+        Ok(sdt)
+    }
+
+    /// Proof of the trie after each item, starting from the inception
+    pub fn history(&self) -> Result<Vec<String>, SdtError> {
+        let suite = self.suite()?;
+        let mut proofs = vec![self.inception_proof(suite)?];
+        for item in self.items().into_iter().skip(1) {
+            let proof = SdtProof::new()
+                .insert_hex("previous", &proofs[proofs.len() - 1])
+                .insert_hex("root", &item.node.gen_proof_with(suite)?)
+                .digest_with(suite)?;
+            proofs.push(proof);
+        }
+        Ok(proofs)
+    }
+
+    fn inception_proof(&self, suite: SdtHashSuite) -> Result<String, SdtError> {
+        let inception_root = self.inception.node.gen_proof_with(suite)?;
+        SdtProof::new()
+            .insert_i64("version", self.version as i64)
+            .insert_str("subject", &self.subject)
+            .insert_hex("root", &inception_root)
+            .digest_with(suite)
+    }
+
+    /// Items of the chain, starting from the inception
+    pub fn items(&self) -> Vec<&SdtItem> {
+        let mut items = vec![&self.inception];
+        while let Some(next) = &items[items.len() - 1].next {
+            items.push(next);
+        }
+        items
+    }
+
+    /// State of the claims after applying every mutation to the inception node
+    pub fn current_node(&self) -> SdtNode {
+        let mut node = SdtNode::new();
+        for item in self.items() {
+            node.merge(&item.node);
         }
-        return Ok(inception_proof);
+        node
+    }
+
+    pub fn to_canonical_string(&self) -> Result<String, SdtError> {
+        utils::to_canonical_string(self)
+    }
+
+    /// Deterministic CBOR encoding with raw digests and salts
+    pub fn to_cbor(&self) -> Result<Vec<u8>, SdtError> {
+        let item = Cbor::Map(vec![
+            (Cbor::text("version"), Cbor::Unsigned(self.version)),
+            (Cbor::text("subject"), Cbor::text(&self.subject)),
+            (Cbor::text("inception"), self.inception.to_cbor()?),
+        ]);
+        Ok(item.encode())
+    }
+
+    pub fn from_cbor(bytes: &[u8]) -> Result<Self, SdtError> {
+        let item = Cbor::decode(bytes)?;
+        let version = match item.get("version") {
+            Some(Cbor::Unsigned(version)) => *version,
+            _ => return Err(SdtError::CborError("Invalid version".to_owned())),
+        };
+        let subject = match item.get("subject") {
+            Some(Cbor::Text(subject)) => subject.to_owned(),
+            _ => return Err(SdtError::CborError("Invalid subject".to_owned())),
+        };
+        let inception = item
+            .get("inception")
+            .ok_or_else(|| SdtError::CborError("Inception is missing".to_owned()))?;
+        Ok(Sdt {
+            version,
+            subject,
+            inception: SdtItem::from_cbor(inception)?,
+        })
     }
 
+    pub fn canonical_eq(&self, other: &Sdt) -> Result<bool, SdtError> {
+        utils::canonical_eq(self, other)
+    }
+
+    /// Checks the proof and the signatures of the mutations. Tries whose inception has
+    /// keys are checked like `verify_authorized`, hiding the keys doesn't hide an unsigned
+    /// mutation.
     pub fn verify(&self, proof: &str) -> Result<bool, SdtError> {
+        self.verify_with(proof, auth::has_keys(&self.inception.node))
+    }
+
+    /// Like `verify`, but every mutation must be shown to be authorized by disclosed keys
+    pub fn verify_authorized(&self, proof: &str) -> Result<bool, SdtError> {
+        self.verify_with(proof, true)
+    }
+
+    /// Like `verify`, but mutations whose keys are hidden by a selection pass unchecked
+    pub fn verify_lenient(&self, proof: &str) -> Result<bool, SdtError> {
+        self.verify_with(proof, false)
+    }
+
+    fn verify_with(&self, proof: &str, strict: bool) -> Result<bool, SdtError> {
         let verified_proof = self.gen_proof()?;
         if verified_proof != proof {
             return Err(SdtError::VerificationError {
@@ -101,6 +337,7 @@ impl Sdt {
                 actual: verified_proof,
             });
         }
+        auth::check(self, strict)?;
         Ok(true)
     }
 }
@@ -158,4 +395,100 @@ mod tests {
         assert_eq!(proof, proof2);
         Ok(())
     }
+
+    #[test]
+    fn position_test() -> Result<(), SdtError> {
+        let claim: SdtClaim = serde_json::from_str(r#"{"personal": {"name": "Adem"}}"#)?;
+        let mutation: SdtClaim = serde_json::from_str(r#"{"personal": {"name": "Ali"}}"#)?;
+        let sdt = Sdt::new("did:p2p:123456", claim.to_node())
+            .mutate(mutation.to_node())
+            .mutate(SdtNode::new())
+            .build();
+        let history = sdt.history()?;
+        for (position, proof) in history.iter().enumerate() {
+            assert_eq!(&sdt.gen_proof_at(position)?, proof);
+            assert!(sdt.verify_at(position, proof)?);
+        }
+        assert!(sdt.verify_at(0, &history[1]).is_err());
+
+        let truncated = sdt.truncate(0)?;
+        assert_eq!(truncated.items().len(), 1);
+        assert_eq!(truncated.current_node().to_claim(), claim);
+        let selected = truncated.select("{\n personal {\n name\n }\n}")?;
+        assert!(selected.verify(&history[0])?);
+        assert_eq!(sdt.truncate(2)?, sdt);
+        assert!(matches!(
+            sdt.truncate(3),
+            Err(SdtError::PositionError {
+                position: 3,
+                len: 3
+            })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn canonical_test() -> Result<(), SdtError> {
+        let claim: SdtClaim = serde_json::from_str(r#"{"b": {"y": 1, "x": 2}, "a": "A"}"#)?;
+        let sdt = Sdt::new("did:p2p:123456", claim.to_node())
+            .mutate(SdtNode::new().add_bool_value("c", true).build())
+            .build();
+        let json = sdt.to_canonical_string()?;
+        let parsed: Sdt = serde_json::from_str(&json)?;
+        assert_eq!(parsed.to_canonical_string()?, json);
+        assert!(sdt.canonical_eq(&parsed)?);
+        assert!(json.starts_with(r#"{"inception":{"next":{"next":null,"node":{"c":"#));
+        assert!(json.ends_with(r#""subject":"did:p2p:123456","version":2}"#));
+        assert_eq!(
+            claim.to_canonical_string()?,
+            r#"{"a":"A","b":{"x":2,"y":1}}"#
+        );
+        assert_eq!(sdt.gen_proof()?, parsed.gen_proof()?);
+        Ok(())
+    }
+
+    #[test]
+    fn version_test() -> Result<(), SdtError> {
+        let claim: SdtClaim =
+            serde_json::from_str(r#"{"personal": {"name": "Adem", "height": 1.80}}"#)?;
+        let mut sdt = Sdt::new("did:p2p:123456", claim.to_node());
+        assert_eq!(sdt.suite()?, SdtHashSuite::JcsSha256);
+        let jcs_proof = sdt.gen_proof()?;
+        let json_proof = sdt.with_suite(SdtHashSuite::JsonSha256).gen_proof()?;
+        assert_ne!(jcs_proof, json_proof);
+        let selected = sdt.select("{\n personal {\n name\n }\n}")?;
+        assert!(selected.verify(&json_proof)?);
+        let cbor_proof = sdt.with_suite(SdtHashSuite::CborSha256).gen_proof()?;
+        assert_ne!(cbor_proof, jcs_proof);
+        assert!(sdt
+            .select("{\n personal {\n name\n }\n}")?
+            .verify(&cbor_proof)?);
+        sdt.version = 0x99;
+        assert!(matches!(
+            sdt.gen_proof(),
+            Err(SdtError::UnsupportedVersion(0x99))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn cbor_test() -> Result<(), SdtError> {
+        let claim: SdtClaim = serde_json::from_str(
+            r#"{"personal": {"name": "Adem", "surname": "Çağlın", "height": 1.80, "age": -5}}"#,
+        )?;
+        let sdt = Sdt::new("did:p2p:123456", claim.to_node())
+            .with_suite(SdtHashSuite::CborSha256)
+            .mutate(SdtNode::new().add_null_value("surname").build())
+            .build();
+        let selected = sdt.select("{\n personal {\n name\n }\n}")?;
+        let encoded = selected.to_cbor()?;
+        let decoded = Sdt::from_cbor(&encoded)?;
+        assert_eq!(decoded, selected);
+        assert!(decoded.verify(&sdt.gen_proof()?)?);
+        assert!(encoded.len() < serde_json::to_vec(&selected)?.len());
+        let mut tampered = encoded.clone();
+        tampered.push(0);
+        assert!(Sdt::from_cbor(&tampered).is_err());
+        Ok(())
+    }
 }
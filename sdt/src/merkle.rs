@@ -0,0 +1,291 @@
+//! Batch anchoring, many subject roots are anchored as the root of one Merkle tree.
+//!
+//! Leaves commit to a subject and its trie root. A node without a sibling is carried to
+//! the next level unchanged, so no leaf is ever duplicated.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    anchor::Anchor,
+    error::SdtError,
+    proof::{SdtHashSuite, SdtProof},
+    Sdt,
+};
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MerkleSide {
+    Left,
+    Right,
+}
+
+/// Sibling hash on the path from a leaf to the aggregate root
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct MerkleStep {
+    pub side: MerkleSide,
+    pub hash: String,
+}
+
+/// Proves that a subject root is part of an anchored aggregate root
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct InclusionProof {
+    pub subject: String,
+    pub root: String,
+    /// Subject the aggregate root is anchored under
+    pub publisher: String,
+    pub aggregate: String,
+    pub path: Vec<MerkleStep>,
+}
+
+/// Collects subject roots and anchors them together.
+#[derive(Debug, Clone)]
+pub struct AnchorAggregator {
+    publisher: String,
+    leaves: Vec<(String, String)>,
+}
+
+impl AnchorAggregator {
+    pub fn new(publisher: &str) -> Self {
+        Self {
+            publisher: publisher.to_owned(),
+            leaves: vec![],
+        }
+    }
+
+    pub fn add(&mut self, subject: &str, root: &str) -> &mut Self {
+        self.leaves.push((subject.to_owned(), root.to_owned()));
+        self
+    }
+
+    pub fn add_sdt(&mut self, sdt: &Sdt) -> Result<&mut Self, SdtError> {
+        Ok(self.add(&sdt.subject, &sdt.gen_proof()?))
+    }
+
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Root of the tree over the collected roots
+    pub fn aggregate(&self) -> Result<String, SdtError> {
+        let levels = self.levels()?;
+        Ok(levels[levels.len() - 1][0].to_owned())
+    }
+
+    /// Publishes the aggregate root and returns an inclusion proof per collected root, in
+    /// the order they were added. The aggregator is empty afterwards.
+    pub fn anchor(&mut self, anchor: &dyn Anchor) -> Result<Vec<InclusionProof>, SdtError> {
+        let levels = self.levels()?;
+        let aggregate = &levels[levels.len() - 1][0];
+        anchor.publish(&self.publisher, aggregate)?;
+        let proofs = self
+            .leaves
+            .iter()
+            .enumerate()
+            .map(|(index, (subject, root))| InclusionProof {
+                subject: subject.to_owned(),
+                root: root.to_owned(),
+                publisher: self.publisher.to_owned(),
+                aggregate: aggregate.to_owned(),
+                path: merkle_path(&levels, index),
+            })
+            .collect();
+        self.leaves.clear();
+        Ok(proofs)
+    }
+
+    /// Hashes of every level of the tree, from the leaves to the aggregate root
+    fn levels(&self) -> Result<Vec<Vec<String>>, SdtError> {
+        if self.leaves.is_empty() {
+            return Err(SdtError::AnchorError("Nothing to aggregate".to_owned()));
+        }
+        let leaves = self
+            .leaves
+            .iter()
+            .map(|(subject, root)| leaf_hash(subject, root))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut levels = vec![leaves];
+        while levels[levels.len() - 1].len() > 1 {
+            let mut next = vec![];
+            for pair in levels[levels.len() - 1].chunks(2) {
+                match pair {
+                    [left, right] => next.push(node_hash(left, right)?),
+                    [single] => next.push(single.to_owned()),
+                    _ => unreachable!(),
+                }
+            }
+            levels.push(next);
+        }
+        Ok(levels)
+    }
+}
+
+impl InclusionProof {
+    /// Aggregate root computed from the subject root and the path
+    pub fn gen_aggregate(&self) -> Result<String, SdtError> {
+        let mut hash = leaf_hash(&self.subject, &self.root)?;
+        for step in &self.path {
+            hash = match step.side {
+                MerkleSide::Left => node_hash(&step.hash, &hash)?,
+                MerkleSide::Right => node_hash(&hash, &step.hash)?,
+            };
+        }
+        Ok(hash)
+    }
+
+    /// Checks the path and that the aggregate root was published by the publisher
+    pub fn verify(&self, anchor: &dyn Anchor) -> Result<(), SdtError> {
+        let aggregate = self.gen_aggregate()?;
+        if aggregate != self.aggregate {
+            return Err(SdtError::VerificationError {
+                expected: self.aggregate.to_owned(),
+                actual: aggregate,
+            });
+        }
+        let anchored = anchor
+            .history(&self.publisher)?
+            .iter()
+            .any(|record| record.root == aggregate);
+        if !anchored {
+            return Err(SdtError::AnchorError(format!(
+                "{aggregate} isn't anchored by {}",
+                self.publisher
+            )));
+        }
+        Ok(())
+    }
+
+    /// Checks that a (selected) trie matches the root of the proof and the proof itself
+    pub fn verify_sdt(&self, sdt: &Sdt, anchor: &dyn Anchor) -> Result<(), SdtError> {
+        if sdt.subject != self.subject {
+            return Err(SdtError::AnchorError(format!(
+                "Proof is for {}, not {}",
+                self.subject, sdt.subject
+            )));
+        }
+        sdt.verify(&self.root)?;
+        self.verify(anchor)
+    }
+}
+
+fn leaf_hash(subject: &str, root: &str) -> Result<String, SdtError> {
+    SdtProof::new()
+        .insert_str("subject", subject)
+        .insert_hex("root", root)
+        .digest_with(SdtHashSuite::JcsSha256)
+}
+
+fn node_hash(left: &str, right: &str) -> Result<String, SdtError> {
+    SdtProof::new()
+        .insert_hex("left", left)
+        .insert_hex("right", right)
+        .digest_with(SdtHashSuite::JcsSha256)
+}
+
+fn merkle_path(levels: &[Vec<String>], mut index: usize) -> Vec<MerkleStep> {
+    let mut path = vec![];
+    for level in &levels[..levels.len() - 1] {
+        let sibling = index ^ 1;
+        if let Some(hash) = level.get(sibling) {
+            let side = if sibling < index {
+                MerkleSide::Left
+            } else {
+                MerkleSide::Right
+            };
+            path.push(MerkleStep {
+                side,
+                hash: hash.to_owned(),
+            });
+        }
+        index /= 2;
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{anchor::FileAnchorLog, node::SdtClaim};
+
+    use super::*;
+
+    fn create_sdt(subject: &str) -> Result<Sdt, SdtError> {
+        let claim: SdtClaim =
+            serde_json::from_str(r#"{"personal": {"name": "Adem", "surname": "Çağlın"}}"#)?;
+        Ok(Sdt::new(subject, claim.to_node()))
+    }
+
+    #[test]
+    fn aggregate_test() -> Result<(), SdtError> {
+        let dir = tempfile::tempdir()?;
+        let log = FileAnchorLog::new(dir.path().join("anchors.jsonl"));
+        for size in [1, 2, 3, 5, 8] {
+            let tries = (0..size)
+                .map(|i| create_sdt(&format!("did:p2p:{i}")))
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut aggregator = AnchorAggregator::new("did:p2p:issuer");
+            for sdt in &tries {
+                aggregator.add_sdt(sdt)?;
+            }
+            let aggregate = aggregator.aggregate()?;
+            let proofs = aggregator.anchor(&log)?;
+            assert!(aggregator.is_empty());
+            assert_eq!(proofs.len(), size);
+            for (sdt, proof) in tries.iter().zip(&proofs) {
+                assert_eq!(proof.aggregate, aggregate);
+                let selected = sdt.select("{\n personal {\n name\n }\n}")?;
+                proof.verify_sdt(&selected, &log)?;
+            }
+        }
+        // one record per batch
+        assert_eq!(log.history("did:p2p:issuer")?.len(), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_proof_test() -> Result<(), SdtError> {
+        let dir = tempfile::tempdir()?;
+        let log = FileAnchorLog::new(dir.path().join("anchors.jsonl"));
+        let first = create_sdt("did:p2p:123456")?;
+        let second = create_sdt("did:p2p:654321")?;
+        let mut aggregator = AnchorAggregator::new("did:p2p:issuer");
+        aggregator
+            .add_sdt(&first)?
+            .add_sdt(&second)?
+            .add("did:p2p:000000", "0x12");
+        let proofs = aggregator.anchor(&log)?;
+
+        assert!(proofs[0].verify_sdt(&second, &log).is_err());
+        let mut swapped = proofs[0].clone();
+        swapped.root = proofs[1].root.to_owned();
+        assert!(matches!(
+            swapped.verify(&log),
+            Err(SdtError::VerificationError { .. })
+        ));
+        let mut tampered = proofs[1].clone();
+        tampered.path[0].side = MerkleSide::Right;
+        assert!(tampered.verify(&log).is_err());
+
+        // an aggregate that was never published
+        let mut aggregator = AnchorAggregator::new("did:p2p:issuer");
+        aggregator.add_sdt(&first)?;
+        let unpublished = InclusionProof {
+            subject: first.subject.to_owned(),
+            root: first.gen_proof()?,
+            publisher: "did:p2p:issuer".to_owned(),
+            aggregate: aggregator.aggregate()?,
+            path: vec![],
+        };
+        assert!(matches!(
+            unpublished.verify_sdt(&first, &log),
+            Err(SdtError::AnchorError(_))
+        ));
+        assert!(matches!(
+            AnchorAggregator::new("did:p2p:issuer").anchor(&log),
+            Err(SdtError::AnchorError(_))
+        ));
+        Ok(())
+    }
+}
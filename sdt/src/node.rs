@@ -1,23 +1,24 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use serde::{Deserialize, Serialize};
 use serde_json::Number;
 
 use crate::{
+    cbor::Cbor,
     error::SdtError,
-    proof::SdtProof,
-    utils::parse_query,
+    proof::{SdtHashSuite, SdtProof},
+    utils::{from_hex_str, parse_query, to_canonical_string, to_hex_str},
     value::{SdtValue, SdtValueKind},
 };
 #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum SdtClaim {
     Value(SdtValueKind),
-    Node(HashMap<String, SdtClaim>),
+    Node(BTreeMap<String, SdtClaim>),
 }
 
 #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
-pub struct SdtNode(HashMap<String, SdtNodeKind>);
+pub struct SdtNode(BTreeMap<String, SdtNodeKind>);
 
 #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -42,23 +43,54 @@ impl SdtClaim {
                 }
             }
         }
-        return node.build();
+        node.build()
+    }
+
+    pub fn to_canonical_string(&self) -> Result<String, SdtError> {
+        to_canonical_string(self)
     }
 }
 
 impl SdtNodeKind {
+    /// serde_json suite, like `SdtValue::gen_proof`
     pub fn gen_proof(&self) -> Result<String, SdtError> {
+        self.gen_proof_with(SdtHashSuite::JsonSha256)
+    }
+
+    pub fn gen_proof_with(&self, suite: SdtHashSuite) -> Result<String, SdtError> {
         match &self {
             Self::Proof(p) => Ok(p.to_owned()),
-            Self::Value(value) => value.gen_proof(),
-            Self::Node(children) => children.gen_proof(),
+            Self::Value(value) => value.gen_proof_with(suite),
+            Self::Node(children) => children.gen_proof_with(suite),
+        }
+    }
+
+    pub fn to_cbor(&self) -> Result<Cbor, SdtError> {
+        match &self {
+            Self::Proof(p) => Ok(Cbor::Bytes(from_hex_str(p)?)),
+            Self::Value(value) => value.to_cbor(),
+            Self::Node(children) => children.to_cbor(),
+        }
+    }
+
+    pub fn from_cbor(item: &Cbor) -> Result<Self, SdtError> {
+        match item {
+            Cbor::Bytes(p) => Ok(Self::Proof(to_hex_str(p))),
+            Cbor::Array(_) => Ok(Self::Value(SdtValue::from_cbor(item)?)),
+            _ => Ok(Self::Node(SdtNode::from_cbor(item)?)),
         }
     }
 }
 
+impl Default for SdtNode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SdtNode {
     pub fn new() -> Self {
-        let map: HashMap<String, SdtNodeKind> = HashMap::new();
+        let map: BTreeMap<String, SdtNodeKind> = BTreeMap::new();
         Self(map)
     }
 
@@ -72,6 +104,11 @@ impl SdtNode {
         self
     }
 
+    pub fn add_sdt_value(&mut self, key: &str, val: SdtValue) -> &mut Self {
+        self.0.insert(key.to_owned(), SdtNodeKind::Value(val));
+        self
+    }
+
     pub fn add_proof(&mut self, key: &str, proof: &str) -> &mut Self {
         self.0
             .insert(key.to_owned(), SdtNodeKind::Proof(proof.to_owned()));
@@ -99,7 +136,7 @@ impl SdtNode {
     }
 
     pub fn to_claim(&self) -> SdtClaim {
-        let mut map: HashMap<String, SdtClaim> = HashMap::new();
+        let mut map: BTreeMap<String, SdtClaim> = BTreeMap::new();
         for (k, v) in &self.0 {
             match v {
                 SdtNodeKind::Value(val) => {
@@ -115,25 +152,89 @@ impl SdtNode {
         SdtClaim::Node(map)
     }
 
+    pub fn to_canonical_string(&self) -> Result<String, SdtError> {
+        to_canonical_string(self)
+    }
+
+    /// serde_json suite, `Sdt::gen_proof` hashes with the suite of the trie version
     pub fn gen_proof(&self) -> Result<String, SdtError> {
+        self.gen_proof_with(SdtHashSuite::JsonSha256)
+    }
+
+    pub fn gen_proof_with(&self, suite: SdtHashSuite) -> Result<String, SdtError> {
         let mut builder = SdtProof::new();
         for (k, v) in &self.0 {
-            builder.insert_str(&k, &v.gen_proof()?);
+            builder.insert_hex(k, &v.gen_proof_with(suite)?);
+        }
+        builder.digest_with(suite)
+    }
+
+    /// Overlays `other` on this node, inner nodes are merged key by key.
+    pub fn merge(&mut self, other: &SdtNode) -> &mut Self {
+        for (k, v) in &other.0 {
+            match (self.0.get_mut(k), v) {
+                (Some(SdtNodeKind::Node(inner)), SdtNodeKind::Node(other_inner)) => {
+                    inner.merge(other_inner);
+                }
+                _ => {
+                    self.0.insert(k.to_owned(), v.to_owned());
+                }
+            }
+        }
+        self
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &SdtNodeKind)> {
+        self.0.iter()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&SdtNodeKind> {
+        self.0.get(key)
+    }
+
+    /// Proofs are raw bytes, values are `[salt, value]` arrays and inner nodes are maps
+    pub fn to_cbor(&self) -> Result<Cbor, SdtError> {
+        let mut entries = vec![];
+        for (k, v) in &self.0 {
+            entries.push((Cbor::text(k), v.to_cbor()?));
         }
-        builder.digest()
+        Ok(Cbor::Map(entries))
     }
 
+    pub fn from_cbor(item: &Cbor) -> Result<Self, SdtError> {
+        let mut node = SdtNode::new();
+        match item {
+            Cbor::Map(entries) => {
+                for (k, v) in entries {
+                    match k {
+                        Cbor::Text(key) => {
+                            node.0.insert(key.to_owned(), SdtNodeKind::from_cbor(v)?);
+                        }
+                        _ => return Err(SdtError::CborError("Key should be text".to_owned())),
+                    }
+                }
+            }
+            _ => return Err(SdtError::CborError("Node should be a map".to_owned())),
+        }
+        Ok(node)
+    }
+
+    /// serde_json suite like `gen_proof`, hidden nodes keep the proof `gen_proof` gives them
     pub fn select(&mut self, query: &str) -> Result<(), SdtError> {
-        let query_keys = parse_query(query);
+        self.select_with(query, SdtHashSuite::JsonSha256)
+    }
+
+    pub fn select_with(&mut self, query: &str, suite: SdtHashSuite) -> Result<(), SdtError> {
+        let query_keys = parse_query(query)?;
         let mut stack: Vec<(String, &mut SdtNode)> = vec![("/".to_owned(), self)];
         while let Some((path, node)) = stack.pop() {
             let mut path_keys: HashMap<String, String> = HashMap::new();
-            for (key, val) in node.0.to_owned() {
-                let path_key = format!("{}{}/", path, key.to_owned());
+            for (key, val) in node.0.clone() {
+                let path_key = format!("{}{}/", path, key);
                 if !query_keys.contains(&path_key) {
                     let matched = query_keys.iter().any(|x| x.starts_with(&path_key));
                     if !matched {
-                        node.add_proof(&key, &val.gen_proof()?);
+                        node.add_proof(&key, &val.gen_proof_with(suite)?);
                     } else {
                         path_keys.insert(key, path_key);
                     }
@@ -280,6 +381,71 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_select_proof() -> Result<(), SdtError> {
+        // 1.0 is written differently by serde_json and JCS
+        let claim: SdtClaim =
+            serde_json::from_str(r#"{"name": "Adem", "personal": {"height": 1.0}}"#)?;
+        let node = claim.to_node();
+        let mut selected = node.clone();
+        selected.select("{\n name\n}")?;
+        assert!(matches!(
+            selected.get("personal"),
+            Some(SdtNodeKind::Proof(_))
+        ));
+        assert_eq!(selected.gen_proof()?, node.gen_proof()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cbor() -> Result<(), SdtError> {
+        let r: SdtNode = serde_json::from_str(
+            r#"
+            {
+                "personal": {
+                    "name": {
+                        "salt": "0x1234567890",
+                        "value": "Adem"
+                    }
+                },
+                "keys": "0x1234567890"
+            }"#,
+        )?;
+        let encoded = r.to_cbor()?.encode();
+        assert_eq!(
+            hex::encode(&encoded),
+            "a2646b65797345123456789068706572736f6e616ca1646e616d6582451234567890644164656d"
+        );
+        assert_eq!(SdtNode::from_cbor(&Cbor::decode(&encoded)?)?, r);
+        assert_ne!(
+            r.gen_proof_with(SdtHashSuite::CborSha256)?,
+            r.gen_proof_with(SdtHashSuite::JcsSha256)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut root = SdtNode::new()
+            .add_node(
+                "personal",
+                SdtNode::new()
+                    .add_str_value("name", "Adem")
+                    .add_str_value("surname", "Çağlın")
+                    .build(),
+            )
+            .add_number_value("age", 5)
+            .build();
+        let mutation = SdtNode::new()
+            .add_node("personal", SdtNode::new().add_null_value("surname").build())
+            .build();
+        root.merge(&mutation);
+        let claim: SdtClaim =
+            serde_json::from_str(r#"{"age": 5, "personal": {"name": "Adem", "surname": null}}"#)
+                .unwrap();
+        assert_eq!(root.to_claim(), claim);
+    }
+
     #[test]
     fn test_new_sdt_node() {
         let sdt_node = SdtNode::new();
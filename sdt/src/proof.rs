@@ -1,18 +1,71 @@
 use std::collections::BTreeMap;
 use serde::{Deserialize, Serialize};
-use crate::{error::SdtError, utils::digest, value::SdtValueKind};
+use crate::{
+    cbor::Cbor,
+    error::SdtError,
+    utils::{digest, digest_cbor, digest_jcs, from_hex_str},
+    value::SdtValueKind,
+};
+
+/// Hashing suite of a trie, identified by the `version` field of `Sdt`.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum SdtHashSuite {
+    /// serde_json + HEX + SHA256
+    JsonSha256 = 0x1,
+    /// JCS (RFC 8785) + HEX + SHA256
+    #[default]
+    JcsSha256 = 0x2,
+    /// Deterministic CBOR (RFC 8949 §4.2) with raw digests and salts + SHA256
+    CborSha256 = 0x3,
+}
+
+/// Hex digests and salts are kept apart from plain values so that binary suites
+/// can hash their raw bytes.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SdtProofValue {
+    Value(SdtValueKind),
+    Hex(String),
+}
 
 #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
-pub struct SdtProof(BTreeMap<String, SdtValueKind>);
+pub struct SdtProof(BTreeMap<String, SdtProofValue>);
+
+impl SdtHashSuite {
+    pub fn from_version(version: u64) -> Result<Self, SdtError> {
+        match version {
+            0x1 => Ok(Self::JsonSha256),
+            0x2 => Ok(Self::JcsSha256),
+            0x3 => Ok(Self::CborSha256),
+            _ => Err(SdtError::UnsupportedVersion(version)),
+        }
+    }
+
+    pub fn version(&self) -> u64 {
+        *self as u64
+    }
+}
+
+impl Default for SdtProof {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl SdtProof {
     pub fn new() -> Self {
-        let body: BTreeMap<String, SdtValueKind> = BTreeMap::new();
+        let body: BTreeMap<String, SdtProofValue> = BTreeMap::new();
         Self(body)
     }
 
     pub fn insert(&mut self, key: &str, value: SdtValueKind) -> &mut Self {
-        self.0.insert(key.to_owned(), value);
+        self.0.insert(key.to_owned(), SdtProofValue::Value(value));
+        self
+    }
+
+    pub fn insert_hex(&mut self, key: &str, hex: &str) -> &mut Self {
+        self.0
+            .insert(key.to_owned(), SdtProofValue::Hex(hex.to_owned()));
         self
     }
 
@@ -24,7 +77,28 @@ impl SdtProof {
         self.insert(key, SdtValueKind::new_i64(v))
     }
 
+    /// serde_json suite, like the standalone `gen_proof` helpers
     pub fn digest(&mut self) -> Result<String, SdtError> {
-        digest(&self.0)
+        self.digest_with(SdtHashSuite::JsonSha256)
+    }
+
+    pub fn digest_with(&mut self, suite: SdtHashSuite) -> Result<String, SdtError> {
+        match suite {
+            SdtHashSuite::JsonSha256 => digest(&self.0),
+            SdtHashSuite::JcsSha256 => digest_jcs(&self.0),
+            SdtHashSuite::CborSha256 => Ok(digest_cbor(&self.to_cbor()?)),
+        }
+    }
+
+    fn to_cbor(&self) -> Result<Cbor, SdtError> {
+        let mut entries = vec![];
+        for (k, v) in &self.0 {
+            let value = match v {
+                SdtProofValue::Value(value) => value.to_cbor(),
+                SdtProofValue::Hex(hex) => Cbor::Bytes(from_hex_str(hex)?),
+            };
+            entries.push((Cbor::text(k), value));
+        }
+        Ok(Cbor::Map(entries))
     }
 }
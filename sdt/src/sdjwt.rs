@@ -0,0 +1,389 @@
+use std::{collections::HashMap, fmt::Display};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    error::SdtError,
+    node::{SdtNode, SdtNodeKind},
+    proof::SdtHashSuite,
+    signer::{SdtSigner, SdtVerifier},
+    utils::{from_hex_str, to_hex_str},
+    value::{SdtValue, SdtValueKind},
+    Sdt,
+};
+
+const SD_ALG: &str = "sha-256";
+const SD_JWT_TYP: &str = "dc+sd-jwt";
+/// Top level claims set by the issuer, they are not part of the trie
+const RESERVED_CLAIMS: [&str; 11] = [
+    "iss",
+    "sub",
+    "aud",
+    "exp",
+    "nbf",
+    "iat",
+    "jti",
+    "cnf",
+    "_sd_alg",
+    "sdt_version",
+    "sdt_root",
+];
+
+/// An SD-JWT (RFC 9901) in its parts: the issuer-signed JWT, the disclosures and an
+/// optional key binding JWT which is kept as is and not verified.
+#[derive(PartialEq, Debug, Clone)]
+pub struct SdJwt {
+    pub jwt: String,
+    pub disclosures: Vec<String>,
+    pub key_binding: Option<String>,
+}
+
+impl SdJwt {
+    /// Exports the current state of the trie with one disclosure per leaf.
+    /// Nested nodes stay visible as objects and hidden leaves of selected tries are left out.
+    pub fn export(sdt: &Sdt, signer: &dyn SdtSigner) -> Result<Self, SdtError> {
+        let mut disclosures = vec![];
+        let mut payload = to_sd_object(&sdt.current_node(), &mut disclosures)?;
+        for claim in RESERVED_CLAIMS {
+            if payload.contains_key(claim) {
+                return Err(SdtError::SdJwtError(format!("{claim} is a reserved claim")));
+            }
+        }
+        payload.insert("sub".to_owned(), json!(sdt.subject));
+        payload.insert("_sd_alg".to_owned(), json!(SD_ALG));
+        payload.insert("sdt_version".to_owned(), json!(sdt.version));
+        payload.insert("sdt_root".to_owned(), json!(sdt.gen_proof()?));
+
+        let mut header = json!({"alg": signer.algorithm(), "typ": SD_JWT_TYP});
+        if let Some(kid) = signer.key_id() {
+            header["kid"] = json!(kid);
+        }
+        let signing_input = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(serde_json::to_vec(&header)?),
+            URL_SAFE_NO_PAD.encode(serde_json::to_vec(&payload)?)
+        );
+        let sig = signer.sign(signing_input.as_bytes())?;
+        Ok(Self {
+            jwt: format!("{}.{}", signing_input, URL_SAFE_NO_PAD.encode(sig)),
+            disclosures,
+            key_binding: None,
+        })
+    }
+
+    pub fn parse(s: &str) -> Result<Self, SdtError> {
+        let mut parts: Vec<&str> = s.split('~').collect();
+        if parts.len() < 2 {
+            return Err(SdtError::SdJwtError(
+                "Missing disclosure separator".to_owned(),
+            ));
+        }
+        let key_binding = parts
+            .pop()
+            .filter(|kb| !kb.is_empty())
+            .map(|kb| kb.to_owned());
+        Ok(Self {
+            jwt: parts[0].to_owned(),
+            disclosures: parts[1..].iter().map(|d| d.to_string()).collect(),
+            key_binding,
+        })
+    }
+
+    /// Verifies the issuer signature and builds a presentation of the disclosed leaves.
+    ///
+    /// Disclosure salts become value salts when they are base64url encoded bytes, so a
+    /// round trip of an exported trie keeps the same inception root. Digests without a
+    /// disclosure cannot be mapped to trie proofs and are dropped.
+    pub fn to_sdt(&self, verifier: &dyn SdtVerifier) -> Result<Sdt, SdtError> {
+        let payload = self.verify(verifier)?;
+        let alg = payload
+            .get("_sd_alg")
+            .and_then(|a| a.as_str())
+            .unwrap_or(SD_ALG);
+        if alg != SD_ALG {
+            return Err(SdtError::SdJwtError(format!("Unsupported _sd_alg {alg}")));
+        }
+        let subject = payload
+            .get("sub")
+            .and_then(|s| s.as_str())
+            .ok_or_else(|| SdtError::SdJwtError("Missing sub claim".to_owned()))?;
+        let version = match payload.get("sdt_version").and_then(|v| v.as_u64()) {
+            Some(version) => version,
+            None => SdtHashSuite::default().version(),
+        };
+
+        let mut disclosures = HashMap::new();
+        for disclosure in &self.disclosures {
+            let parsed = parse_disclosure(disclosure)?;
+            if disclosures.insert(sd_digest(disclosure), parsed).is_some() {
+                return Err(SdtError::SdJwtError("Duplicate disclosure".to_owned()));
+            }
+        }
+        let mut claims = payload.clone();
+        for claim in RESERVED_CLAIMS {
+            claims.remove(claim);
+        }
+        let node = from_sd_object(&claims, &mut disclosures)?;
+        if !disclosures.is_empty() {
+            return Err(SdtError::SdJwtError(
+                "Disclosure is not referenced by the payload".to_owned(),
+            ));
+        }
+        let mut sdt = Sdt::new(subject, node);
+        sdt.version = version;
+        Ok(sdt)
+    }
+
+    fn verify(&self, verifier: &dyn SdtVerifier) -> Result<Map<String, Value>, SdtError> {
+        let parts: Vec<&str> = self.jwt.split('.').collect();
+        if parts.len() != 3 {
+            return Err(SdtError::SdJwtError("Invalid jwt".to_owned()));
+        }
+        let header: Value = serde_json::from_slice(&decode_b64(parts[0])?)?;
+        let alg = header
+            .get("alg")
+            .and_then(|a| a.as_str())
+            .ok_or_else(|| SdtError::SdJwtError("Missing alg header".to_owned()))?;
+        match header.get("typ").and_then(|t| t.as_str()) {
+            Some(SD_JWT_TYP) => {}
+            typ => {
+                return Err(SdtError::SdJwtError(format!(
+                    "Unsupported typ header {}",
+                    typ.unwrap_or_default()
+                )))
+            }
+        }
+        let kid = header.get("kid").and_then(|k| k.as_str());
+        let signing_input = format!("{}.{}", parts[0], parts[1]);
+        verifier.verify(alg, kid, signing_input.as_bytes(), &decode_b64(parts[2])?)?;
+        Ok(serde_json::from_slice(&decode_b64(parts[1])?)?)
+    }
+}
+
+impl Display for SdJwt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}~", self.jwt)?;
+        for disclosure in &self.disclosures {
+            write!(f, "{}~", disclosure)?;
+        }
+        if let Some(kb) = &self.key_binding {
+            write!(f, "{}", kb)?;
+        }
+        Ok(())
+    }
+}
+
+fn to_sd_object(
+    node: &SdtNode,
+    disclosures: &mut Vec<String>,
+) -> Result<Map<String, Value>, SdtError> {
+    let mut object = Map::new();
+    let mut digests = vec![];
+    for (key, kind) in node.iter() {
+        if key == "_sd" || key == "..." {
+            return Err(SdtError::SdJwtError(format!("{key} is a reserved claim")));
+        }
+        match kind {
+            SdtNodeKind::Value(val) => {
+                let salt = URL_SAFE_NO_PAD.encode(from_hex_str(&val.salt)?);
+                let disclosure =
+                    URL_SAFE_NO_PAD.encode(serde_json::to_vec(&json!([salt, key, val.value]))?);
+                digests.push(sd_digest(&disclosure));
+                disclosures.push(disclosure);
+            }
+            SdtNodeKind::Node(inner) => {
+                object.insert(
+                    key.to_owned(),
+                    Value::Object(to_sd_object(inner, disclosures)?),
+                );
+            }
+            SdtNodeKind::Proof(_) => {}
+        }
+    }
+    if !digests.is_empty() {
+        digests.sort();
+        object.insert("_sd".to_owned(), json!(digests));
+    }
+    Ok(object)
+}
+
+fn from_sd_object(
+    object: &Map<String, Value>,
+    disclosures: &mut HashMap<String, (String, String, SdtValueKind)>,
+) -> Result<SdtNode, SdtError> {
+    let mut node = SdtNode::new();
+    for (key, value) in object {
+        match value {
+            Value::Array(digests) if key == "_sd" => {
+                for digest in digests {
+                    let digest = digest
+                        .as_str()
+                        .ok_or_else(|| SdtError::SdJwtError("Invalid _sd digest".to_owned()))?;
+                    if let Some((salt, name, value)) = disclosures.remove(digest) {
+                        if object.contains_key(&name) || node.get(&name).is_some() {
+                            return Err(SdtError::SdJwtError(format!("Duplicate claim {name}")));
+                        }
+                        node.add_sdt_value(
+                            &name,
+                            SdtValue {
+                                salt: map_salt(&salt),
+                                value,
+                            },
+                        );
+                    }
+                }
+            }
+            Value::Object(inner) => {
+                let inner_node = from_sd_object(inner, disclosures)?;
+                if inner_node.iter().next().is_some() {
+                    node.add_node(key, inner_node);
+                }
+            }
+            _ => {
+                let value: SdtValueKind = serde_json::from_value(value.clone())
+                    .map_err(|_| SdtError::SdJwtError(format!("Unsupported claim {key}")))?;
+                node.add_value(key, value);
+            }
+        }
+    }
+    Ok(node)
+}
+
+fn parse_disclosure(disclosure: &str) -> Result<(String, String, SdtValueKind), SdtError> {
+    let items: Vec<Value> = serde_json::from_slice(&decode_b64(disclosure)?)?;
+    match items.as_slice() {
+        [Value::String(salt), Value::String(name), value] => {
+            if name == "_sd" || name == "..." {
+                return Err(SdtError::SdJwtError(format!("{name} is a reserved claim")));
+            }
+            let value: SdtValueKind = serde_json::from_value(value.clone())
+                .map_err(|_| SdtError::SdJwtError(format!("Unsupported value of {name}")))?;
+            Ok((salt.to_owned(), name.to_owned(), value))
+        }
+        _ => Err(SdtError::SdJwtError(
+            "Only object property disclosures are supported".to_owned(),
+        )),
+    }
+}
+
+/// Base64url salts keep their bytes, any other salt is replaced by a fresh one.
+fn map_salt(salt: &str) -> String {
+    match URL_SAFE_NO_PAD.decode(salt) {
+        Ok(bytes) if !bytes.is_empty() => to_hex_str(bytes),
+        _ => SdtValue::new(SdtValueKind::Null).salt,
+    }
+}
+
+fn sd_digest(disclosure: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(disclosure.as_bytes()))
+}
+
+fn decode_b64(s: &str) -> Result<Vec<u8>, SdtError> {
+    URL_SAFE_NO_PAD
+        .decode(s)
+        .map_err(|e| SdtError::SdJwtError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{node::SdtClaim, signer::Ed25519Signer};
+
+    fn create_sdt() -> Result<Sdt, SdtError> {
+        let claim: SdtClaim = serde_json::from_str(
+            r#"{"personal": {"name": "Adem", "surname": "Çağlın", "age": 5}, "verified": true}"#,
+        )?;
+        let mutation: SdtClaim = serde_json::from_str(r#"{"personal": {"age": 6}}"#)?;
+        Ok(Sdt::new("did:p2p:123456", claim.to_node())
+            .mutate(mutation.to_node())
+            .build())
+    }
+
+    #[test]
+    fn export_import_test() -> Result<(), SdtError> {
+        let sdt = create_sdt()?;
+        let signer = Ed25519Signer::new().with_key_id("did:p2p:issuer#key-1");
+        let sd_jwt = SdJwt::export(&sdt, &signer)?;
+        assert_eq!(sd_jwt.disclosures.len(), 4);
+        let parsed = SdJwt::parse(&sd_jwt.to_string())?;
+        assert_eq!(parsed, sd_jwt);
+
+        let imported = parsed.to_sdt(&signer.verifier())?;
+        assert_eq!(imported.subject, sdt.subject);
+        assert_eq!(imported.inception.node, sdt.current_node());
+        assert_eq!(
+            imported.gen_proof()?,
+            Sdt::new(&sdt.subject, sdt.current_node()).gen_proof()?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn partial_disclosure_test() -> Result<(), SdtError> {
+        let sdt = create_sdt()?;
+        let signer = Ed25519Signer::new();
+        let mut sd_jwt = SdJwt::export(&sdt, &signer)?;
+        let name = sd_jwt
+            .disclosures
+            .iter()
+            .position(|d| {
+                String::from_utf8(decode_b64(d).unwrap())
+                    .unwrap()
+                    .contains("\"name\"")
+            })
+            .unwrap();
+        sd_jwt.disclosures.remove(name);
+        let imported = sd_jwt.to_sdt(&signer.verifier())?;
+        let claim: SdtClaim = serde_json::from_str(
+            r#"{"personal": {"surname": "Çağlın", "age": 6}, "verified": true}"#,
+        )?;
+        assert_eq!(imported.inception.node.to_claim(), claim);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_sd_jwt_test() -> Result<(), SdtError> {
+        let sdt = create_sdt()?;
+        let signer = Ed25519Signer::new();
+        let sd_jwt = SdJwt::export(&sdt, &signer)?;
+        assert!(matches!(
+            sd_jwt.to_sdt(&Ed25519Signer::new().verifier()),
+            Err(SdtError::InvalidSignature)
+        ));
+
+        let mut unreferenced = sd_jwt.clone();
+        unreferenced
+            .disclosures
+            .push(URL_SAFE_NO_PAD.encode(r#"["c2FsdA", "extra", 1]"#));
+        assert!(unreferenced.to_sdt(&signer.verifier()).is_err());
+
+        let mut duplicated = sd_jwt.clone();
+        duplicated.disclosures.push(sd_jwt.disclosures[0].clone());
+        assert!(matches!(
+            duplicated.to_sdt(&signer.verifier()),
+            Err(SdtError::SdJwtError(ref e)) if e == "Duplicate disclosure"
+        ));
+
+        let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"EdDSA","typ":"JWT"}"#);
+        let payload = sd_jwt.jwt.split('.').nth(1).unwrap();
+        let signing_input = format!("{header}.{payload}");
+        let sig = URL_SAFE_NO_PAD.encode(signer.sign(signing_input.as_bytes())?);
+        let untyped = SdJwt {
+            jwt: format!("{signing_input}.{sig}"),
+            ..sd_jwt.clone()
+        };
+        assert!(matches!(
+            untyped.to_sdt(&signer.verifier()),
+            Err(SdtError::SdJwtError(ref e)) if e == "Unsupported typ header JWT"
+        ));
+
+        let reserved = Sdt::new(
+            "did:p2p:123456",
+            SdtNode::new().add_node("sub", SdtNode::new()).build(),
+        );
+        assert!(SdJwt::export(&reserved, &signer).is_err());
+        Ok(())
+    }
+}
@@ -1,14 +1,97 @@
-use crate::{error::SdtError, node::SdtClaim, Sdt};
+use std::collections::HashMap;
+
+use crate::{
+    compact::{self, SdtArmor},
+    error::{SdtError, SdtErrorCategory, SdtErrorDetails},
+    node::SdtClaim,
+    utils::create_random,
+    Sdt,
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "cmd", content = "payload")]
 pub enum SdtInput {
-    Inception { subject: String, claim: SdtClaim },
-    Mutation { sdt: Sdt, claim: SdtClaim },
-    Selection { sdt: Sdt, query: String },
-    Proof(Sdt),
-    Verification { sdt: Sdt, proof: String },
+    /// Keeps the trie in the session and returns its handle when `store` is set
+    Inception {
+        subject: String,
+        claim: SdtClaim,
+        #[serde(default)]
+        store: bool,
+    },
+    /// Mutating a handle updates the stored trie
+    Mutation {
+        sdt: SdtRef,
+        claim: SdtClaim,
+    },
+    Selection {
+        sdt: SdtRef,
+        query: String,
+    },
+    Proof(SdtRef),
+    Verification {
+        sdt: SdtRef,
+        proof: String,
+    },
+    Encode {
+        sdt: SdtRef,
+        #[serde(default)]
+        armor: SdtArmor,
+    },
+    Decode(String),
+    /// Independent commands, a failed command doesn't stop the others
+    Batch(Vec<SdtInput>),
+    /// Steps where the trie of each result feeds the next step, stops at the first error
+    Pipeline {
+        #[serde(default)]
+        sdt: Option<SdtRef>,
+        steps: Vec<SdtStep>,
+    },
+    /// Stores a trie in the session
+    Import(Sdt),
+    /// Returns a stored trie
+    Export(String),
+    /// Removes a stored trie from the session
+    Evict(String),
+}
+
+/// A trie carried by the input or a handle of a trie stored in the session
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SdtRef {
+    Inline(Sdt),
+    Handle { handle: String },
+}
+
+impl From<Sdt> for SdtRef {
+    fn from(sdt: Sdt) -> Self {
+        SdtRef::Inline(sdt)
+    }
+}
+
+/// A pipeline command, the trie comes from the pipeline input or a previous step
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "cmd", content = "payload")]
+pub enum SdtStep {
+    Inception {
+        subject: String,
+        claim: SdtClaim,
+    },
+    Mutation {
+        claim: SdtClaim,
+    },
+    Selection {
+        query: String,
+    },
+    Proof,
+    Verification {
+        proof: String,
+    },
+    Encode {
+        #[serde(default)]
+        armor: SdtArmor,
+    },
+    Decode(String),
 }
 
 #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
@@ -17,45 +100,303 @@ pub enum SdtResult {
     Inception(Sdt),
     Mutation(Sdt),
     Selection(Sdt),
-    Proof(String),
-    Verification(bool),
-    Error(String),
+    Proof {
+        proof: String,
+    },
+    Verification {
+        valid: bool,
+    },
+    Encode {
+        data: String,
+    },
+    Decode(Sdt),
+    /// `code` is the stable identifier of the error, see `SdtError::code`
+    Error {
+        message: String,
+        code: String,
+        category: SdtErrorCategory,
+        #[serde(default, skip_serializing_if = "SdtErrorDetails::is_empty")]
+        details: SdtErrorDetails,
+    },
+    Batch {
+        results: Vec<SdtResult>,
+    },
+    Pipeline {
+        results: Vec<SdtResult>,
+    },
+    /// Handle and root proof of a trie stored in the session
+    Stored {
+        handle: String,
+        proof: String,
+    },
+    Export(Sdt),
+    Evicted {
+        handle: String,
+    },
+}
+
+impl SdtInput {
+    /// Whether the input stores tries in a session or reads them by handle,
+    /// any other input can run without a session
+    pub fn needs_session(&self) -> bool {
+        match self {
+            SdtInput::Inception { store, .. } => *store,
+            SdtInput::Mutation { sdt, .. }
+            | SdtInput::Selection { sdt, .. }
+            | SdtInput::Proof(sdt)
+            | SdtInput::Verification { sdt, .. }
+            | SdtInput::Encode { sdt, .. } => matches!(sdt, SdtRef::Handle { .. }),
+            SdtInput::Decode(_) => false,
+            SdtInput::Batch(inputs) => inputs.iter().any(SdtInput::needs_session),
+            SdtInput::Pipeline { sdt, .. } => matches!(sdt, Some(SdtRef::Handle { .. })),
+            SdtInput::Import(_) | SdtInput::Export(_) | SdtInput::Evict(_) => true,
+        }
+    }
 }
 
+impl SdtStep {
+    fn into_input(self, sdt: Option<Sdt>) -> Result<SdtInput, SdtError> {
+        let sdt = || {
+            sdt.map(SdtRef::Inline)
+                .ok_or_else(|| SdtError::PipelineError("Step needs a trie".to_owned()))
+        };
+        let input = match self {
+            SdtStep::Inception { subject, claim } => SdtInput::Inception {
+                subject,
+                claim,
+                store: false,
+            },
+            SdtStep::Mutation { claim } => SdtInput::Mutation { sdt: sdt()?, claim },
+            SdtStep::Selection { query } => SdtInput::Selection { sdt: sdt()?, query },
+            SdtStep::Proof => SdtInput::Proof(sdt()?),
+            SdtStep::Verification { proof } => SdtInput::Verification { sdt: sdt()?, proof },
+            SdtStep::Encode { armor } => SdtInput::Encode { sdt: sdt()?, armor },
+            SdtStep::Decode(data) => SdtInput::Decode(data),
+        };
+        Ok(input)
+    }
+}
+
+impl SdtResult {
+    /// The trie of inception, mutation, selection, decode and export results
+    pub fn sdt(&self) -> Option<&Sdt> {
+        match self {
+            SdtResult::Inception(sdt)
+            | SdtResult::Mutation(sdt)
+            | SdtResult::Selection(sdt)
+            | SdtResult::Decode(sdt)
+            | SdtResult::Export(sdt) => Some(sdt),
+            _ => None,
+        }
+    }
+
+    fn to_output(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_else(|err| {
+            let err = SdtError::from(err);
+            serde_json::json!({
+                "kind": "Error",
+                "message": err.to_string(),
+                "code": err.code(),
+                "category": err.category(),
+            })
+            .to_string()
+        })
+    }
+}
+
+impl From<SdtError> for SdtResult {
+    fn from(err: SdtError) -> Self {
+        SdtResult::Error {
+            message: err.to_string(),
+            code: err.code().to_owned(),
+            category: err.category(),
+            details: err.details(),
+        }
+    }
+}
+
+/// Executes a single input without a session, handles are rejected.
 pub struct SdtService(pub String);
 
 impl SdtService {
     pub fn execute(&self) -> String {
-        let res = match self.execute_inner() {
-            Ok(res) => res,
-            Err(err) => SdtResult::Error(err.to_string()),
+        let res = match serde_json::from_str(&self.0) {
+            Ok(input) => run(input, None),
+            Err(err) => SdtResult::from(SdtError::from(err)),
         };
-        match serde_json::to_string_pretty(&res) {
-            Ok(s) => s,
-            Err(e) => format!(r#"{{"kind": "Error","message": {}}}"#, e.to_string()),
+        res.to_output()
+    }
+}
+
+/// In-memory documents referenced by handles across service calls.
+///
+/// Stored tries stay until they are evicted, `with_limit` bounds their number.
+#[derive(Default, Debug)]
+pub struct SdtSession {
+    documents: HashMap<String, Sdt>,
+    limit: Option<usize>,
+}
+
+impl SdtSession {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rejects inceptions and imports once `limit` tries are stored
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            documents: HashMap::new(),
+            limit: Some(limit),
         }
     }
 
-    fn execute_inner(&self) -> Result<SdtResult, SdtError> {
-        let input: SdtInput = serde_json::from_str(&self.0)?;
-        let result = match input {
-            SdtInput::Inception { subject, claim } => {
-                SdtResult::Inception(Sdt::new(&subject, claim.to_node()))
-            }
-            SdtInput::Mutation { sdt, claim } => {
-                let mut sdt_clone = sdt.clone();
-                SdtResult::Mutation(sdt_clone.mutate(claim.to_node()).build())
+    pub fn execute(&mut self, input: &str) -> String {
+        let res = match serde_json::from_str(input) {
+            Ok(input) => run(input, Some(self)),
+            Err(err) => SdtResult::from(SdtError::from(err)),
+        };
+        res.to_output()
+    }
+
+    pub fn get(&self, handle: &str) -> Option<&Sdt> {
+        self.documents.get(handle)
+    }
+
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    fn insert(&mut self, sdt: Sdt) -> Result<SdtResult, SdtError> {
+        if let Some(limit) = self.limit.filter(|limit| self.documents.len() >= *limit) {
+            return Err(SdtError::SessionFull(limit));
+        }
+        let handle = hex::encode(create_random::<16>());
+        let proof = sdt.gen_proof()?;
+        self.documents.insert(handle.clone(), sdt);
+        Ok(SdtResult::Stored { handle, proof })
+    }
+
+    fn get_mut(&mut self, handle: &str) -> Result<&mut Sdt, SdtError> {
+        self.documents
+            .get_mut(handle)
+            .ok_or_else(|| unknown_handle(handle))
+    }
+
+    fn remove(&mut self, handle: &str) -> Result<Sdt, SdtError> {
+        self.documents
+            .remove(handle)
+            .ok_or_else(|| unknown_handle(handle))
+    }
+}
+
+fn run(input: SdtInput, session: Option<&mut SdtSession>) -> SdtResult {
+    run_inner(input, session).unwrap_or_else(SdtResult::from)
+}
+
+fn run_inner(input: SdtInput, mut session: Option<&mut SdtSession>) -> Result<SdtResult, SdtError> {
+    let result = match input {
+        SdtInput::Inception {
+            subject,
+            claim,
+            store,
+        } => {
+            let sdt = Sdt::new(&subject, claim.to_node());
+            if store {
+                session_of(session)?.insert(sdt)?
+            } else {
+                SdtResult::Inception(sdt)
             }
-            SdtInput::Selection { sdt, query } => {
-                let mut sdt_clone = sdt.clone();
-                sdt_clone.select(&query)?;
-                SdtResult::Selection(sdt_clone.build())
+        }
+        SdtInput::Mutation {
+            sdt: SdtRef::Handle { handle },
+            claim,
+        } => {
+            let sdt = session_of(session)?.get_mut(&handle)?;
+            sdt.mutate(claim.to_node());
+            SdtResult::Stored {
+                proof: sdt.gen_proof()?,
+                handle,
             }
-            SdtInput::Proof(sdt) => SdtResult::Proof(sdt.gen_proof()?),
-            SdtInput::Verification { sdt, proof } => SdtResult::Verification(sdt.verify(&proof)?),
+        }
+        SdtInput::Mutation { sdt, claim } => {
+            let mut sdt_clone = resolve(sdt, session)?;
+            SdtResult::Mutation(sdt_clone.mutate(claim.to_node()).build())
+        }
+        // `select` returns the selected trie, the input is left as it is
+        SdtInput::Selection { sdt, query } => {
+            SdtResult::Selection(resolve(sdt, session)?.select(&query)?)
+        }
+        SdtInput::Proof(sdt) => SdtResult::Proof {
+            proof: resolve(sdt, session)?.gen_proof()?,
+        },
+        SdtInput::Verification { sdt, proof } => SdtResult::Verification {
+            valid: resolve(sdt, session)?.verify(&proof)?,
+        },
+        SdtInput::Encode { sdt, armor } => SdtResult::Encode {
+            data: compact::encode(&resolve(sdt, session)?, armor)?,
+        },
+        SdtInput::Decode(data) => SdtResult::Decode(compact::decode(&data)?),
+        SdtInput::Batch(inputs) => SdtResult::Batch {
+            results: inputs
+                .into_iter()
+                .map(|input| run(input, session.as_deref_mut()))
+                .collect(),
+        },
+        SdtInput::Pipeline { sdt, steps } => {
+            let sdt = match sdt {
+                Some(sdt) => Some(resolve(sdt, session)?),
+                None => None,
+            };
+            run_pipeline(sdt, steps)
+        }
+        SdtInput::Import(sdt) => session_of(session)?.insert(sdt)?,
+        SdtInput::Export(handle) => {
+            SdtResult::Export(session_of(session)?.get_mut(&handle)?.clone())
+        }
+        SdtInput::Evict(handle) => {
+            session_of(session)?.remove(&handle)?;
+            SdtResult::Evicted { handle }
+        }
+    };
+    Ok(result)
+}
+
+fn unknown_handle(handle: &str) -> SdtError {
+    SdtError::UnknownHandle(handle.to_owned())
+}
+
+fn session_of(session: Option<&mut SdtSession>) -> Result<&mut SdtSession, SdtError> {
+    session.ok_or(SdtError::SessionRequired)
+}
+
+fn resolve(sdt: SdtRef, session: Option<&mut SdtSession>) -> Result<Sdt, SdtError> {
+    match sdt {
+        SdtRef::Inline(sdt) => Ok(sdt),
+        SdtRef::Handle { handle } => Ok(session_of(session)?.get_mut(&handle)?.clone()),
+    }
+}
+
+fn run_pipeline(mut sdt: Option<Sdt>, steps: Vec<SdtStep>) -> SdtResult {
+    let mut results = vec![];
+    for step in steps {
+        let result = match step.into_input(sdt.clone()) {
+            Ok(input) => run(input, None),
+            Err(err) => SdtResult::from(err),
         };
-        Ok(result)
+        if let Some(next) = result.sdt() {
+            sdt = Some(next.clone());
+        }
+        let failed = matches!(result, SdtResult::Error { .. });
+        results.push(result);
+        if failed {
+            break;
+        }
     }
+    SdtResult::Pipeline { results }
 }
 
 #[cfg(test)]
@@ -67,4 +408,239 @@ mod tests {
         let r = "bb".as_bytes() > "aa".as_bytes();
         eprintln!("{r}");
     }
+
+    #[test]
+    fn deterministic_output_test() -> Result<(), SdtError> {
+        let claim: SdtClaim = serde_json::from_str(
+            r#"{"personal": {"name": "Adem", "surname": "Çağlın"}, "age": 5}"#,
+        )?;
+        let sdt = Sdt::new("did:p2p:123456", claim.to_node());
+        let query = "{\n personal {\n name\n }\n }";
+        let input = SdtInput::Selection {
+            sdt: sdt.clone().into(),
+            query: query.to_owned(),
+        };
+        let input_str = serde_json::to_string(&input)?;
+        let output = SdtService(input_str.clone()).execute();
+        let selected: Sdt = serde_json::from_str(&output)?;
+        assert_eq!(selected, sdt.select(query)?);
+        assert_ne!(selected, sdt);
+        for _ in 0..5 {
+            assert_eq!(SdtService(input_str.clone()).execute(), output);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn encode_decode_test() -> Result<(), SdtError> {
+        let claim: SdtClaim = serde_json::from_str(r#"{"personal": {"name": "Adem"}}"#)?;
+        let sdt = Sdt::new("did:p2p:123456", claim.to_node());
+        let input = format!(
+            r#"{{"cmd": "Encode", "payload": {{"sdt": {}}}}}"#,
+            serde_json::to_string(&sdt)?
+        );
+        let data = match serde_json::from_str(&SdtService(input).execute())? {
+            SdtResult::Encode { data } => data,
+            res => panic!("Unexpected result {res:?}"),
+        };
+        assert!(data.starts_with("SDT1:"));
+        let input = serde_json::to_string(&SdtInput::Decode(data))?;
+        let result: SdtResult = serde_json::from_str(&SdtService(input).execute())?;
+        assert_eq!(result, SdtResult::Decode(sdt));
+        Ok(())
+    }
+
+    #[test]
+    fn proof_verification_test() -> Result<(), SdtError> {
+        let claim: SdtClaim = serde_json::from_str(r#"{"personal": {"name": "Adem"}}"#)?;
+        let sdt = Sdt::new("did:p2p:123456", claim.to_node());
+        let input = serde_json::to_string(&SdtInput::Proof(sdt.clone().into()))?;
+        let proof = match serde_json::from_str(&SdtService(input).execute())? {
+            SdtResult::Proof { proof } => proof,
+            res => panic!("Unexpected result {res:?}"),
+        };
+        let input = serde_json::to_string(&SdtInput::Verification {
+            sdt: sdt.clone().into(),
+            proof,
+        })?;
+        let result: SdtResult = serde_json::from_str(&SdtService(input).execute())?;
+        assert_eq!(result, SdtResult::Verification { valid: true });
+        let input = serde_json::to_string(&SdtInput::Verification {
+            sdt: sdt.into(),
+            proof: "0x12".to_owned(),
+        })?;
+        let result: SdtResult = serde_json::from_str(&SdtService(input).execute())?;
+        match result {
+            SdtResult::Error {
+                code,
+                category,
+                details,
+                ..
+            } => {
+                assert_eq!(code, "verification_failed");
+                assert_eq!(category, SdtErrorCategory::Verification);
+                assert_eq!(details.expected.as_deref(), Some("0x12"));
+                assert!(details.actual.is_some());
+            }
+            res => panic!("Unexpected result {res:?}"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn error_output_test() -> Result<(), SdtError> {
+        let output: serde_json::Value =
+            serde_json::from_str(&SdtService("{".to_owned()).execute())?;
+        assert_eq!(output["kind"], "Error");
+        assert_eq!(output["code"], "invalid_json");
+        assert_eq!(output["category"], "input");
+        assert_eq!(output["details"]["line"], 1);
+
+        let input = r#"{"cmd": "Selection", "payload": {"sdt": {"handle": "1234"}, "query": "{"}}"#;
+        let output: serde_json::Value =
+            serde_json::from_str(&SdtService(input.to_owned()).execute())?;
+        assert_eq!(output["code"], "session_required");
+        assert!(output.get("details").is_none());
+
+        let claim: SdtClaim = serde_json::from_str(r#"{"personal": {"name": "Adem"}}"#)?;
+        let input = serde_json::to_string(&SdtInput::Selection {
+            sdt: Sdt::new("did:p2p:123456", claim.to_node()).into(),
+            query: "{\n personal {\n name\n }\n}\n}".to_owned(),
+        })?;
+        let output: serde_json::Value = serde_json::from_str(&SdtService(input).execute())?;
+        assert_eq!(output["code"], "invalid_query");
+        assert_eq!(output["category"], "query");
+        assert_eq!(output["details"]["line"], 6);
+        assert_eq!(output["details"]["column"], 1);
+        Ok(())
+    }
+
+    #[test]
+    fn batch_test() -> Result<(), SdtError> {
+        let input = r#"{"cmd": "Batch", "payload": [
+            {"cmd": "Inception", "payload": {"subject": "did:p2p:123456", "claim": {"name": "Adem"}}},
+            {"cmd": "Decode", "payload": "SDT1:%%%"},
+            {"cmd": "Inception", "payload": {"subject": "did:p2p:654321", "claim": {}}}
+        ]}"#;
+        let results = match serde_json::from_str(&SdtService(input.to_owned()).execute())? {
+            SdtResult::Batch { results } => results,
+            res => panic!("Unexpected result {res:?}"),
+        };
+        assert_eq!(results.len(), 3);
+        assert!(matches!(results[0], SdtResult::Inception(_)));
+        assert!(matches!(results[1], SdtResult::Error { .. }));
+        assert!(matches!(results[2], SdtResult::Inception(_)));
+        Ok(())
+    }
+
+    #[test]
+    fn pipeline_test() -> Result<(), SdtError> {
+        let input = r#"{"cmd": "Pipeline", "payload": {"steps": [
+            {"cmd": "Inception", "payload": {"subject": "did:p2p:123456", "claim": {"personal": {"name": "Adem", "surname": "Çağlın"}}}},
+            {"cmd": "Mutation", "payload": {"claim": {"personal": {"surname": null}}}},
+            {"cmd": "Proof"},
+            {"cmd": "Selection", "payload": {"query": "{\n personal {\n name\n }\n}"}},
+            {"cmd": "Proof"}
+        ]}}"#;
+        let results = match serde_json::from_str(&SdtService(input.to_owned()).execute())? {
+            SdtResult::Pipeline { results } => results,
+            res => panic!("Unexpected result {res:?}"),
+        };
+        assert_eq!(results.len(), 5);
+        let selected = results[3].sdt().unwrap();
+        assert_eq!(selected.items().len(), 2);
+        assert_eq!(results[2], results[4]);
+
+        let steps = vec![
+            SdtStep::Verification {
+                proof: "0x12".to_owned(),
+            },
+            SdtStep::Proof,
+        ];
+        let input = serde_json::to_string(&SdtInput::Pipeline {
+            sdt: Some(selected.clone().into()),
+            steps,
+        })?;
+        let results = match serde_json::from_str(&SdtService(input).execute())? {
+            SdtResult::Pipeline { results } => results,
+            res => panic!("Unexpected result {res:?}"),
+        };
+        assert_eq!(results.len(), 1);
+        assert!(matches!(results[0], SdtResult::Error { .. }));
+
+        let input = r#"{"cmd": "Pipeline", "payload": {"steps": [{"cmd": "Proof"}]}}"#;
+        let results = match serde_json::from_str(&SdtService(input.to_owned()).execute())? {
+            SdtResult::Pipeline { results } => results,
+            res => panic!("Unexpected result {res:?}"),
+        };
+        assert!(matches!(results[0], SdtResult::Error { .. }));
+        Ok(())
+    }
+
+    #[test]
+    fn session_test() -> Result<(), SdtError> {
+        let mut session = SdtSession::new();
+        let input = r#"{"cmd": "Inception", "payload": {"subject": "did:p2p:123456", "claim": {"personal": {"name": "Adem", "surname": "Çağlın"}}, "store": true}}"#;
+        let handle = match serde_json::from_str(&session.execute(input))? {
+            SdtResult::Stored { handle, .. } => handle,
+            res => panic!("Unexpected result {res:?}"),
+        };
+        let input = format!(
+            r#"{{"cmd": "Mutation", "payload": {{"sdt": {{"handle": "{handle}"}}, "claim": {{"personal": {{"surname": null}}}}}}}}"#
+        );
+        let proof = match serde_json::from_str(&session.execute(&input))? {
+            SdtResult::Stored { proof, .. } => proof,
+            res => panic!("Unexpected result {res:?}"),
+        };
+        assert_eq!(session.get(&handle).unwrap().items().len(), 2);
+
+        let input = format!(r#"{{"cmd": "Proof", "payload": {{"handle": "{handle}"}}}}"#);
+        let result: SdtResult = serde_json::from_str(&session.execute(&input))?;
+        assert_eq!(result, SdtResult::Proof { proof });
+
+        let input = format!(r#"{{"cmd": "Export", "payload": "{handle}"}}"#);
+        let exported = match serde_json::from_str(&session.execute(&input))? {
+            SdtResult::Export(sdt) => sdt,
+            res => panic!("Unexpected result {res:?}"),
+        };
+        let input = format!(r#"{{"cmd": "Evict", "payload": "{handle}"}}"#);
+        let result: SdtResult = serde_json::from_str(&session.execute(&input))?;
+        assert_eq!(result, SdtResult::Evicted { handle });
+        assert!(session.is_empty());
+        let result: SdtResult = serde_json::from_str(&session.execute(&input))?;
+        assert!(matches!(result, SdtResult::Error { ref code, .. } if code == "unknown_handle"));
+
+        let input = serde_json::to_string(&SdtInput::Import(exported.clone()))?;
+        let handle = match serde_json::from_str(&session.execute(&input))? {
+            SdtResult::Stored { handle, .. } => handle,
+            res => panic!("Unexpected result {res:?}"),
+        };
+        assert_eq!(session.get(&handle), Some(&exported));
+        Ok(())
+    }
+
+    #[test]
+    fn session_limit_test() -> Result<(), SdtError> {
+        let claim: SdtClaim = serde_json::from_str(r#"{"name": "Adem"}"#)?;
+        let input = serde_json::to_string(&SdtInput::Import(Sdt::new(
+            "did:p2p:123456",
+            claim.to_node(),
+        )))?;
+        let mut session = SdtSession::with_limit(1);
+        let result: SdtResult = serde_json::from_str(&session.execute(&input))?;
+        assert!(matches!(result, SdtResult::Stored { .. }));
+        let result: SdtResult = serde_json::from_str(&session.execute(&input))?;
+        assert!(matches!(result, SdtResult::Error { ref code, .. } if code == "session_full"));
+
+        // handles need a session
+        assert!(serde_json::from_str::<SdtInput>(&input)?.needs_session());
+        let result: SdtResult = serde_json::from_str(&SdtService(input).execute())?;
+        assert!(matches!(result, SdtResult::Error { .. }));
+        let proof = SdtInput::Proof(SdtRef::Handle {
+            handle: "0x12".to_owned(),
+        });
+        assert!(SdtInput::Batch(vec![SdtInput::Decode(String::new()), proof]).needs_session());
+        assert!(!SdtInput::Decode(String::new()).needs_session());
+        Ok(())
+    }
 }
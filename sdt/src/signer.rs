@@ -0,0 +1,132 @@
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+
+use crate::{error::SdtError, utils::create_random};
+
+/// Signs envelopes (SD-JWT, credentials, receipts) on behalf of a key.
+pub trait SdtSigner {
+    /// JOSE algorithm name, e.g. `EdDSA`
+    fn algorithm(&self) -> String;
+    fn key_id(&self) -> Option<String>;
+    fn sign(&self, msg: &[u8]) -> Result<Vec<u8>, SdtError>;
+}
+
+/// Checks signatures produced by an `SdtSigner`.
+pub trait SdtVerifier {
+    fn verify(
+        &self,
+        algorithm: &str,
+        key_id: Option<&str>,
+        msg: &[u8],
+        sig: &[u8],
+    ) -> Result<(), SdtError>;
+}
+
+pub const ED25519_ALGORITHM: &str = "EdDSA";
+
+pub struct Ed25519Signer {
+    key: SigningKey,
+    key_id: Option<String>,
+}
+
+pub struct Ed25519Verifier {
+    key: VerifyingKey,
+}
+
+impl Ed25519Signer {
+    pub fn new() -> Self {
+        Self::from_secret(&create_random::<32>())
+    }
+
+    pub fn from_secret(secret: &[u8; 32]) -> Self {
+        Self {
+            key: SigningKey::from_bytes(secret),
+            key_id: None,
+        }
+    }
+
+    pub fn with_key_id(mut self, key_id: &str) -> Self {
+        self.key_id = Some(key_id.to_owned());
+        self
+    }
+
+    pub fn public_key(&self) -> [u8; 32] {
+        self.key.verifying_key().to_bytes()
+    }
+
+    pub fn verifier(&self) -> Ed25519Verifier {
+        Ed25519Verifier {
+            key: self.key.verifying_key(),
+        }
+    }
+}
+
+impl Default for Ed25519Signer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SdtSigner for Ed25519Signer {
+    fn algorithm(&self) -> String {
+        ED25519_ALGORITHM.to_owned()
+    }
+
+    fn key_id(&self) -> Option<String> {
+        self.key_id.clone()
+    }
+
+    fn sign(&self, msg: &[u8]) -> Result<Vec<u8>, SdtError> {
+        Ok(self.key.sign(msg).to_bytes().to_vec())
+    }
+}
+
+impl Ed25519Verifier {
+    pub fn from_public(public: &[u8]) -> Result<Self, SdtError> {
+        let bytes: [u8; 32] = public.try_into().map_err(|_| {
+            SdtError::InvalidKey("Ed25519 public key should be 32 bytes".to_owned())
+        })?;
+        let key =
+            VerifyingKey::from_bytes(&bytes).map_err(|e| SdtError::InvalidKey(e.to_string()))?;
+        Ok(Self { key })
+    }
+
+    pub fn public_key(&self) -> [u8; 32] {
+        self.key.to_bytes()
+    }
+}
+
+impl SdtVerifier for Ed25519Verifier {
+    fn verify(
+        &self,
+        algorithm: &str,
+        _key_id: Option<&str>,
+        msg: &[u8],
+        sig: &[u8],
+    ) -> Result<(), SdtError> {
+        if algorithm != ED25519_ALGORITHM {
+            return Err(SdtError::InvalidSignature);
+        }
+        let sig = Signature::from_slice(sig).map_err(|_| SdtError::InvalidSignature)?;
+        self.key
+            .verify(msg, &sig)
+            .map_err(|_| SdtError::InvalidSignature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_verify_test() -> Result<(), SdtError> {
+        let signer = Ed25519Signer::new().with_key_id("key-1");
+        let sig = signer.sign(b"message")?;
+        let verifier = Ed25519Verifier::from_public(&signer.public_key())?;
+        verifier.verify(ED25519_ALGORITHM, Some("key-1"), b"message", &sig)?;
+        assert!(verifier
+            .verify(ED25519_ALGORITHM, None, b"other", &sig)
+            .is_err());
+        assert!(Ed25519Verifier::from_public(&[0u8; 31]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,413 @@
+//! Revocation of issued tries with status lists, in the style of W3C Bitstring Status Lists.
+//!
+//! The issuer commits a `status` entry in the inception node, pointing to an index of a list
+//! it manages. Setting the bit revokes (or suspends) the trie without touching the chain,
+//! verifiers fetch the list and check the bit.
+
+use std::{
+    fs::{self, File},
+    io::{ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::SdtError,
+    node::{SdtNode, SdtNodeKind},
+    value::{SdtValue, SdtValueKind},
+    Sdt,
+};
+
+pub const STATUS: &str = "status";
+/// 16KB, lists smaller than this reveal too much about whose status is fetched
+pub const DEFAULT_STATUS_LIST_SIZE: usize = 131_072;
+/// Multibase prefix of base64url
+const ENCODING_PREFIX: &str = "u";
+/// Upper bound of an inflated list, guards against decompression bombs
+const MAX_LIST_BYTES: u64 = 16 * 1024 * 1024;
+const FILE_EXTENSION: &str = "txt";
+const PURPOSE_EXTENSION: &str = "purpose";
+
+/// A bitstring of statuses, index 0 is the most significant bit of the first byte
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct StatusList {
+    bits: Vec<u8>,
+}
+
+impl StatusList {
+    /// Every status is unset, `len` is rounded up to whole bytes
+    pub fn new(len: usize) -> Self {
+        StatusList {
+            bits: vec![0; len.div_ceil(8)],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.bits.len() * 8
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Result<bool, SdtError> {
+        let byte = self.byte(index)?;
+        Ok(self.bits[byte] & mask(index) != 0)
+    }
+
+    /// The list doesn't know its purpose, `FileStatusStore::publish` keeps revocations set
+    pub fn set(&mut self, index: usize, status: bool) -> Result<&mut Self, SdtError> {
+        let byte = self.byte(index)?;
+        if status {
+            self.bits[byte] |= mask(index);
+        } else {
+            self.bits[byte] &= !mask(index);
+        }
+        Ok(self)
+    }
+
+    /// Multibase base64url of the gzipped bits, the `encodedList` of a status list credential
+    pub fn encode(&self) -> Result<String, SdtError> {
+        let mut encoder = GzEncoder::new(vec![], Compression::best());
+        encoder.write_all(&self.bits)?;
+        Ok(format!(
+            "{ENCODING_PREFIX}{}",
+            URL_SAFE_NO_PAD.encode(encoder.finish()?)
+        ))
+    }
+
+    pub fn decode(encoded: &str) -> Result<Self, SdtError> {
+        let data = encoded
+            .strip_prefix(ENCODING_PREFIX)
+            .ok_or_else(|| status_error("List should be multibase base64url"))?;
+        let compressed = URL_SAFE_NO_PAD
+            .decode(data)
+            .map_err(|e| status_error(&e.to_string()))?;
+        let mut bits = vec![];
+        GzDecoder::new(compressed.as_slice())
+            .take(MAX_LIST_BYTES + 1)
+            .read_to_end(&mut bits)
+            .map_err(|e| status_error(&e.to_string()))?;
+        if bits.len() as u64 > MAX_LIST_BYTES {
+            return Err(status_error("List is too large"));
+        }
+        Ok(StatusList { bits })
+    }
+
+    fn byte(&self, index: usize) -> Result<usize, SdtError> {
+        if index >= self.len() {
+            return Err(status_error(&format!(
+                "Index {index} is out of range, the list has {} entries",
+                self.len()
+            )));
+        }
+        Ok(index / 8)
+    }
+}
+
+fn mask(index: usize) -> u8 {
+    0x80 >> (index % 8)
+}
+
+/// What a set bit means
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatusPurpose {
+    /// Permanent, a published revocation list can't unset a bit
+    #[default]
+    Revocation,
+    /// Can be unset again
+    Suspension,
+}
+
+impl StatusPurpose {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StatusPurpose::Revocation => "revocation",
+            StatusPurpose::Suspension => "suspension",
+        }
+    }
+
+    fn parse(s: &str) -> Result<Self, SdtError> {
+        match s {
+            "revocation" => Ok(StatusPurpose::Revocation),
+            "suspension" => Ok(StatusPurpose::Suspension),
+            _ => Err(status_error(&format!("Unknown purpose {s}"))),
+        }
+    }
+}
+
+/// Position of a trie in a status list, committed under `status` in the inception node
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+pub struct StatusEntry {
+    /// Identifier of the list, e.g. its URL
+    pub list: String,
+    pub index: usize,
+    pub purpose: StatusPurpose,
+}
+
+impl StatusEntry {
+    pub fn new(list: &str, index: usize, purpose: StatusPurpose) -> Self {
+        StatusEntry {
+            list: list.to_owned(),
+            index,
+            purpose,
+        }
+    }
+
+    /// Adds the entry to an inception node before the trie is created
+    pub fn add_to<'a>(&self, node: &'a mut SdtNode) -> &'a mut SdtNode {
+        let entry = SdtNode::new()
+            .add_str_value("list", &self.list)
+            .add_number_value("index", self.index as i64)
+            .add_str_value("purpose", self.purpose.as_str())
+            .build();
+        node.add_node(STATUS, entry)
+    }
+
+    /// The entry of the inception, `None` when the trie has none
+    pub fn from_sdt(sdt: &Sdt) -> Result<Option<Self>, SdtError> {
+        let entry = match sdt.inception.node.get(STATUS) {
+            Some(SdtNodeKind::Node(entry)) => entry,
+            Some(SdtNodeKind::Proof(_)) => return Err(status_error("Status entry is hidden")),
+            Some(SdtNodeKind::Value(_)) => return Err(status_error("Status should be a node")),
+            None => return Ok(None),
+        };
+        let index = match value(entry, "index")? {
+            SdtValueKind::Number(index) => index.as_u64(),
+            _ => None,
+        }
+        .ok_or_else(|| status_error("Index should be a positive integer"))?;
+        Ok(Some(StatusEntry {
+            list: string_value(entry, "list")?.to_owned(),
+            index: index as usize,
+            purpose: StatusPurpose::parse(string_value(entry, "purpose")?)?,
+        }))
+    }
+}
+
+fn value<'a>(entry: &'a SdtNode, key: &str) -> Result<&'a SdtValueKind, SdtError> {
+    match entry.get(key) {
+        Some(SdtNodeKind::Value(SdtValue { value, .. })) => Ok(value),
+        Some(SdtNodeKind::Proof(_)) => Err(status_error(&format!("Status {key} is hidden"))),
+        _ => Err(status_error(&format!("Status {key} is missing"))),
+    }
+}
+
+fn string_value<'a>(entry: &'a SdtNode, key: &str) -> Result<&'a str, SdtError> {
+    match value(entry, key)? {
+        SdtValueKind::String(s) => Ok(s),
+        _ => Err(status_error(&format!("Status {key} should be a string"))),
+    }
+}
+
+/// Retrieves the current version of a status list, e.g. over HTTP
+pub trait StatusFetcher {
+    fn fetch(&self, list: &str) -> Result<StatusList, SdtError>;
+}
+
+/// Status lists kept by the issuer in a directory, one file of the encoded list per list
+/// and one of its purpose, which is fixed by the first publish
+pub struct FileStatusStore {
+    dir: PathBuf,
+}
+
+impl FileStatusStore {
+    /// Creates the directory if it doesn't exist
+    pub fn new<P: AsRef<Path>>(dir: P) -> Result<Self, SdtError> {
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir: dir.as_ref().to_owned(),
+        })
+    }
+
+    /// Replaces the published version of the list, a revocation list has to keep every bit
+    /// of the published version set
+    pub fn publish(
+        &self,
+        list: &str,
+        status_list: &StatusList,
+        purpose: StatusPurpose,
+    ) -> Result<(), SdtError> {
+        let purpose_path = self.path(list).with_extension(PURPOSE_EXTENSION);
+        let published_purpose = match fs::read_to_string(&purpose_path) {
+            Ok(published) => Some(StatusPurpose::parse(published.trim())?),
+            Err(err) if err.kind() == ErrorKind::NotFound => None,
+            Err(err) => return Err(err.into()),
+        };
+        match published_purpose {
+            Some(published) if published != purpose => {
+                return Err(status_error(&format!(
+                    "{list} is a {} list",
+                    published.as_str()
+                )));
+            }
+            Some(_) => {}
+            None => write_file(&purpose_path, purpose.as_str())?,
+        }
+        if purpose == StatusPurpose::Revocation {
+            if let Some(published) = self.read(list)? {
+                let reinstated = status_list.bits.len() < published.bits.len()
+                    || published
+                        .bits
+                        .iter()
+                        .zip(&status_list.bits)
+                        .any(|(old, new)| old & !new != 0);
+                if reinstated {
+                    return Err(status_error(&format!(
+                        "Revocations of {list} can't be undone"
+                    )));
+                }
+            }
+        }
+        write_file(&self.path(list), &status_list.encode()?)
+    }
+
+    fn read(&self, list: &str) -> Result<Option<StatusList>, SdtError> {
+        match fs::read_to_string(self.path(list)) {
+            Ok(encoded) => Ok(Some(StatusList::decode(encoded.trim())?)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Lists are hex encoded, URLs contain characters file systems reject
+    fn path(&self, list: &str) -> PathBuf {
+        self.dir
+            .join(hex::encode(list))
+            .with_extension(FILE_EXTENSION)
+    }
+}
+
+impl StatusFetcher for FileStatusStore {
+    fn fetch(&self, list: &str) -> Result<StatusList, SdtError> {
+        self.read(list)?
+            .ok_or_else(|| status_error(&format!("Unknown status list {list}")))
+    }
+}
+
+/// Replaces a file by renaming, readers never see a partial write
+fn write_file(path: &Path, contents: &str) -> Result<(), SdtError> {
+    let tmp = path.with_extension("tmp");
+    let mut file = File::create(&tmp)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(tmp, path)?;
+    Ok(())
+}
+
+/// Checks the status bit of a (selected) trie, the `status` entry has to be disclosed.
+///
+/// Returns the checked entry, tries without an entry pass with `None`.
+pub fn verify(sdt: &Sdt, fetcher: &dyn StatusFetcher) -> Result<Option<StatusEntry>, SdtError> {
+    let Some(entry) = StatusEntry::from_sdt(sdt)? else {
+        return Ok(None);
+    };
+    if fetcher.fetch(&entry.list)?.get(entry.index)? {
+        return Err(SdtError::Revoked {
+            list: entry.list,
+            index: entry.index,
+            purpose: entry.purpose.as_str().to_owned(),
+        });
+    }
+    Ok(Some(entry))
+}
+
+fn status_error(msg: &str) -> SdtError {
+    SdtError::StatusError(msg.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIST: &str = "https://example.com/status/1";
+    const SUSPENSION_LIST: &str = "https://example.com/status/2";
+
+    fn create_sdt(entry: &StatusEntry) -> Sdt {
+        let personal = SdtNode::new().add_str_value("name", "Adem").build();
+        let mut root = SdtNode::new().add_node("personal", personal).build();
+        entry.add_to(&mut root);
+        Sdt::new("did:p2p:123456", root)
+    }
+
+    #[test]
+    fn list_test() -> Result<(), SdtError> {
+        let mut list = StatusList::new(DEFAULT_STATUS_LIST_SIZE);
+        list.set(0, true)?.set(94567, true)?.set(0, false)?;
+        assert!(!list.get(0)? && list.get(94567)? && !list.get(94568)?);
+        assert!(list.get(DEFAULT_STATUS_LIST_SIZE).is_err());
+
+        let encoded = list.encode()?;
+        assert!(encoded.len() < 200, "{} chars", encoded.len());
+        assert_eq!(StatusList::decode(&encoded)?, list);
+        assert_eq!(StatusList::new(9).len(), 16);
+        for invalid in ["H4sI", "u!!", "uH4sI"] {
+            assert!(StatusList::decode(invalid).is_err(), "{invalid}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn verify_test() -> Result<(), SdtError> {
+        let dir = tempfile::tempdir()?;
+        let store = FileStatusStore::new(dir.path())?;
+        let entry = StatusEntry::new(LIST, 42, StatusPurpose::Revocation);
+        let sdt = create_sdt(&entry);
+        let selected = sdt.select("{\n personal {\n name\n }\n status\n}")?;
+        assert!(matches!(
+            verify(&selected, &store),
+            Err(SdtError::StatusError(_))
+        ));
+
+        let mut list = StatusList::new(DEFAULT_STATUS_LIST_SIZE);
+        store.publish(LIST, &list, entry.purpose)?;
+        assert_eq!(verify(&selected, &store)?, Some(entry.clone()));
+        list.set(42, true)?;
+        store.publish(LIST, &list, entry.purpose)?;
+        assert!(matches!(
+            verify(&selected, &store),
+            Err(SdtError::Revoked { index: 42, .. })
+        ));
+        list.set(42, false)?;
+        assert!(matches!(
+            store.publish(LIST, &list, entry.purpose),
+            Err(SdtError::StatusError(_))
+        ));
+        assert!(store
+            .publish(LIST, &StatusList::new(8), entry.purpose)
+            .is_err());
+        assert!(verify(&selected, &store).is_err());
+        // the purpose of a list is fixed by its first publish
+        assert!(matches!(
+            store.publish(LIST, &list, StatusPurpose::Suspension),
+            Err(SdtError::StatusError(_))
+        ));
+        assert!(verify(&selected, &store).is_err());
+
+        // suspensions can be lifted
+        let suspension = StatusEntry::new(SUSPENSION_LIST, 42, StatusPurpose::Suspension);
+        let suspended = create_sdt(&suspension).select("{\n status\n}")?;
+        list.set(42, true)?;
+        store.publish(SUSPENSION_LIST, &list, suspension.purpose)?;
+        assert!(matches!(
+            verify(&suspended, &store),
+            Err(SdtError::Revoked { index: 42, .. })
+        ));
+        list.set(42, false)?;
+        store.publish(SUSPENSION_LIST, &list, suspension.purpose)?;
+        assert_eq!(verify(&suspended, &store)?, Some(suspension));
+
+        let hidden = sdt.select("{\n personal {\n name\n }\n}")?;
+        assert!(matches!(
+            verify(&hidden, &store),
+            Err(SdtError::StatusError(_))
+        ));
+        let unlisted = Sdt::new("did:p2p:123456", SdtNode::new());
+        assert_eq!(verify(&unlisted, &store)?, None);
+        Ok(())
+    }
+}
@@ -0,0 +1,394 @@
+//! Persistence of tries by subject.
+//!
+//! Chains only grow: a write is rejected unless it extends the stored chain, so
+//! concurrent writers can't fork a chain, the slower one gets a `StoreConflict`.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::{error::SdtError, node::SdtNode, signer::SdtSigner, Sdt};
+
+pub trait SdtStore {
+    /// Stores the chain of a new subject or a chain extending the stored one, the chain has
+    /// to verify
+    fn put(&self, sdt: &Sdt) -> Result<(), SdtError>;
+    fn get(&self, subject: &str) -> Result<Option<Sdt>, SdtError>;
+    /// Appends a mutation signed by `signers` if `head` is still the proof of the stored
+    /// chain, returns the new head. Tries with keys need signers of a current key.
+    fn append(
+        &self,
+        subject: &str,
+        head: &str,
+        node: SdtNode,
+        signers: &[&dyn SdtSigner],
+    ) -> Result<String, SdtError>;
+    fn subjects(&self) -> Result<Vec<String>, SdtError>;
+
+    /// Proof of the stored chain after each item, starting from the inception
+    fn history(&self, subject: &str) -> Result<Vec<String>, SdtError> {
+        self.get(subject)?
+            .ok_or_else(|| SdtError::UnknownSubject(subject.to_owned()))?
+            .history()
+    }
+}
+
+/// Chains kept in memory, e.g. for tests and short lived services.
+#[derive(Default, Debug)]
+pub struct SdtMemoryStore {
+    chains: Mutex<HashMap<String, Sdt>>,
+}
+
+impl SdtMemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SdtStore for SdtMemoryStore {
+    fn put(&self, sdt: &Sdt) -> Result<(), SdtError> {
+        let mut chains = self.chains.lock().unwrap_or_else(|e| e.into_inner());
+        check_extends(chains.get(&sdt.subject), sdt)?;
+        chains.insert(sdt.subject.to_owned(), sdt.clone());
+        Ok(())
+    }
+
+    fn get(&self, subject: &str) -> Result<Option<Sdt>, SdtError> {
+        let chains = self.chains.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(chains.get(subject).cloned())
+    }
+
+    fn append(
+        &self,
+        subject: &str,
+        head: &str,
+        node: SdtNode,
+        signers: &[&dyn SdtSigner],
+    ) -> Result<String, SdtError> {
+        let mut chains = self.chains.lock().unwrap_or_else(|e| e.into_inner());
+        let sdt = chains
+            .get_mut(subject)
+            .ok_or_else(|| SdtError::UnknownSubject(subject.to_owned()))?;
+        // the mutation is applied to a copy, a failed proof leaves the chain untouched
+        let mut next = sdt.clone();
+        let proof = append_to(&mut next, head, node, signers)?;
+        *sdt = next;
+        Ok(proof)
+    }
+
+    fn subjects(&self) -> Result<Vec<String>, SdtError> {
+        let chains = self.chains.lock().unwrap_or_else(|e| e.into_inner());
+        let mut subjects: Vec<String> = chains.keys().cloned().collect();
+        subjects.sort();
+        Ok(subjects)
+    }
+}
+
+/// One JSON file per subject in a directory.
+///
+/// Writes hold an exclusive lock on the directory, so processes sharing the directory
+/// don't fork chains either. Files are replaced by renaming, readers never see a partial
+/// write.
+#[derive(Debug)]
+pub struct SdtFileStore {
+    dir: PathBuf,
+}
+
+const FILE_EXTENSION: &str = "json";
+const LOCK_FILE: &str = ".lock";
+
+impl SdtFileStore {
+    /// Creates the directory if it doesn't exist
+    pub fn new<P: AsRef<Path>>(dir: P) -> Result<Self, SdtError> {
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir: dir.as_ref().to_owned(),
+        })
+    }
+
+    /// Subjects are hex encoded, DIDs contain characters file systems reject
+    fn path(&self, subject: &str) -> PathBuf {
+        self.dir
+            .join(hex::encode(subject))
+            .with_extension(FILE_EXTENSION)
+    }
+
+    fn read(&self, subject: &str) -> Result<Option<Sdt>, SdtError> {
+        match fs::read(self.path(subject)) {
+            Ok(data) => serde_json::from_slice(&data).map(Some).map_err(|e| {
+                SdtError::StoreError(format!("Invalid file of subject {subject}: {e}"))
+            }),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn write(&self, sdt: &Sdt) -> Result<(), SdtError> {
+        let path = self.path(&sdt.subject);
+        let tmp = path.with_extension("tmp");
+        let mut file = File::create(&tmp)?;
+        file.write_all(serde_json::to_string(sdt)?.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(tmp, path)?;
+        // the rename is only durable once the directory entry is
+        #[cfg(unix)]
+        File::open(&self.dir)?.sync_all()?;
+        Ok(())
+    }
+
+    /// Exclusive lock of the directory, released when the file is dropped
+    fn lock(&self) -> Result<File, SdtError> {
+        let file = File::create(self.dir.join(LOCK_FILE))?;
+        file.lock()?;
+        Ok(file)
+    }
+}
+
+impl SdtStore for SdtFileStore {
+    fn put(&self, sdt: &Sdt) -> Result<(), SdtError> {
+        let _lock = self.lock()?;
+        check_extends(self.read(&sdt.subject)?.as_ref(), sdt)?;
+        self.write(sdt)
+    }
+
+    fn get(&self, subject: &str) -> Result<Option<Sdt>, SdtError> {
+        self.read(subject)
+    }
+
+    fn append(
+        &self,
+        subject: &str,
+        head: &str,
+        node: SdtNode,
+        signers: &[&dyn SdtSigner],
+    ) -> Result<String, SdtError> {
+        let _lock = self.lock()?;
+        let mut sdt = self
+            .read(subject)?
+            .ok_or_else(|| SdtError::UnknownSubject(subject.to_owned()))?;
+        let proof = append_to(&mut sdt, head, node, signers)?;
+        self.write(&sdt)?;
+        Ok(proof)
+    }
+
+    fn subjects(&self) -> Result<Vec<String>, SdtError> {
+        let mut subjects = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == FILE_EXTENSION) {
+                if let Some(name) = path.file_stem().and_then(|name| name.to_str()) {
+                    let subject = hex::decode(name)
+                        .map_err(|e| e.to_string())
+                        .and_then(|bytes| String::from_utf8(bytes).map_err(|e| e.to_string()))
+                        .map_err(|e| {
+                            SdtError::StoreError(format!("Invalid subject file name {name}: {e}"))
+                        })?;
+                    subjects.push(subject);
+                }
+            }
+        }
+        subjects.sort();
+        Ok(subjects)
+    }
+}
+
+/// A chain may replace the stored one only if it verifies and the stored head is one of its
+/// proofs
+fn check_extends(stored: Option<&Sdt>, sdt: &Sdt) -> Result<(), SdtError> {
+    sdt.verify(&sdt.gen_proof()?)?;
+    let Some(stored) = stored else {
+        return Ok(());
+    };
+    let stored_head = stored.gen_proof()?;
+    let position = stored.items().len() - 1;
+    let history = sdt.history()?;
+    match history.get(position) {
+        Some(proof) if proof == &stored_head => Ok(()),
+        proof => Err(SdtError::StoreConflict {
+            subject: sdt.subject.to_owned(),
+            expected: proof.unwrap_or(&history[history.len() - 1]).to_owned(),
+            actual: stored_head,
+        }),
+    }
+}
+
+fn append_to(
+    sdt: &mut Sdt,
+    head: &str,
+    node: SdtNode,
+    signers: &[&dyn SdtSigner],
+) -> Result<String, SdtError> {
+    let current = sdt.gen_proof()?;
+    if current != head {
+        return Err(SdtError::StoreConflict {
+            subject: sdt.subject.to_owned(),
+            expected: head.to_owned(),
+            actual: current,
+        });
+    }
+    let proof = sdt.mutate_signed(node, signers)?.gen_proof()?;
+    sdt.verify(&proof)?;
+    Ok(proof)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread};
+
+    use crate::{
+        auth::{ed25519_key, ASSERTION_METHOD, KEYS},
+        node::SdtClaim,
+        signer::Ed25519Signer,
+    };
+
+    use super::*;
+
+    fn incept(subject: &str) -> Result<Sdt, SdtError> {
+        let claim: SdtClaim = serde_json::from_str(r#"{"personal": {"name": "Adem"}}"#)?;
+        Ok(Sdt::new(subject, claim.to_node()))
+    }
+
+    fn mutation() -> Result<SdtNode, SdtError> {
+        let claim: SdtClaim = serde_json::from_str(r#"{"personal": {"name": null}}"#)?;
+        Ok(claim.to_node())
+    }
+
+    fn check_store<S: SdtStore>(store: &S) -> Result<(), SdtError> {
+        let sdt = incept("did:p2p:123456")?;
+        let inception = sdt.gen_proof()?;
+        store.put(&sdt)?;
+        store.put(&incept("did:p2p:654321")?)?;
+        assert_eq!(store.subjects()?, vec!["did:p2p:123456", "did:p2p:654321"]);
+        assert_eq!(store.get("did:p2p:123456")?, Some(sdt.clone()));
+        assert_eq!(store.get("did:p2p:000000")?, None);
+
+        let head = store.append("did:p2p:123456", &inception, mutation()?, &[])?;
+        assert_eq!(
+            store.history("did:p2p:123456")?,
+            vec![inception.clone(), head.clone()]
+        );
+        // a writer still holding the inception as head would fork the chain
+        let err = store
+            .append("did:p2p:123456", &inception, mutation()?, &[])
+            .unwrap_err();
+        assert!(matches!(err, SdtError::StoreConflict { ref actual, .. } if actual == &head));
+        assert!(matches!(
+            store.put(&incept("did:p2p:123456")?),
+            Err(SdtError::StoreConflict { .. })
+        ));
+        assert!(matches!(
+            store.append("did:p2p:000000", &inception, mutation()?, &[]),
+            Err(SdtError::UnknownSubject(_))
+        ));
+
+        // a chain extending the stored one replaces it
+        let mut stored = store.get("did:p2p:123456")?.unwrap();
+        let extended = stored.mutate(SdtNode::new()).build();
+        store.put(&extended)?;
+        assert_eq!(store.history("did:p2p:123456")?.len(), 3);
+        assert!(matches!(
+            store.put(&sdt),
+            Err(SdtError::StoreConflict { .. })
+        ));
+        Ok(())
+    }
+
+    fn check_concurrent_appends<S: SdtStore + Send + Sync + 'static>(
+        store: Arc<S>,
+    ) -> Result<(), SdtError> {
+        let sdt = incept("did:p2p:123456")?;
+        let head = sdt.gen_proof()?;
+        store.put(&sdt)?;
+        let writers: Vec<_> = (0..8)
+            .map(|_| {
+                let store = store.clone();
+                let head = head.clone();
+                thread::spawn(move || store.append("did:p2p:123456", &head, SdtNode::new(), &[]))
+            })
+            .collect();
+        let appended = writers
+            .into_iter()
+            .map(|writer| writer.join().unwrap())
+            .filter(Result::is_ok)
+            .count();
+        assert_eq!(appended, 1);
+        assert_eq!(store.history("did:p2p:123456")?.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn memory_store_test() -> Result<(), SdtError> {
+        check_store(&SdtMemoryStore::new())?;
+        check_concurrent_appends(Arc::new(SdtMemoryStore::new()))
+    }
+
+    #[test]
+    fn file_store_test() -> Result<(), SdtError> {
+        let dir = tempfile::tempdir()?;
+        check_store(&SdtFileStore::new(dir.path())?)?;
+        // a second store on the same directory sees the chains of the first
+        let store = SdtFileStore::new(dir.path())?;
+        assert_eq!(store.history("did:p2p:123456")?.len(), 3);
+        fs::write(dir.path().join("notes.json"), "{}")?;
+        assert!(matches!(store.subjects(), Err(SdtError::StoreError(_))));
+        let garbage = format!("{}.json", hex::encode("did:p2p:000000"));
+        fs::write(dir.path().join(garbage), "{\"version\": ")?;
+        assert!(matches!(
+            store.get("did:p2p:000000"),
+            Err(SdtError::StoreError(_))
+        ));
+
+        let dir = tempfile::tempdir()?;
+        check_concurrent_appends(Arc::new(SdtFileStore::new(dir.path())?))
+    }
+
+    #[test]
+    fn keyed_store_test() -> Result<(), SdtError> {
+        let signer = Ed25519Signer::new().with_key_id("key_1");
+        let key = SdtNode::new()
+            .add_str_value("key_1", &ed25519_key(&signer.public_key()))
+            .build();
+        let keys = SdtNode::new().add_node(ASSERTION_METHOD, key).build();
+        let sdt = Sdt::new(
+            "did:p2p:123456",
+            SdtNode::new().add_node(KEYS, keys).build(),
+        );
+        let inception = sdt.gen_proof()?;
+        let dir = tempfile::tempdir()?;
+        let file_store = SdtFileStore::new(dir.path())?;
+        let stores: [&dyn SdtStore; 2] = [&SdtMemoryStore::new(), &file_store];
+        for store in stores {
+            store.put(&sdt)?;
+            assert!(matches!(
+                store.append("did:p2p:123456", &inception, mutation()?, &[]),
+                Err(SdtError::AuthorizationError { position: 1, .. })
+            ));
+            assert_eq!(store.history("did:p2p:123456")?, vec![inception.clone()]);
+            let unsigned = sdt.clone().mutate(mutation()?).build();
+            assert!(matches!(
+                store.put(&unsigned),
+                Err(SdtError::AuthorizationError { position: 1, .. })
+            ));
+            let head = store.append("did:p2p:123456", &inception, mutation()?, &[&signer])?;
+            let stored = store.get("did:p2p:123456")?.unwrap();
+            assert!(stored.verify(&head)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn history_test() -> Result<(), SdtError> {
+        let mut sdt = incept("did:p2p:123456")?;
+        let inception = sdt.gen_proof()?;
+        sdt.mutate(mutation()?).mutate(SdtNode::new());
+        let history = sdt.history()?;
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0], inception);
+        assert_eq!(history[2], sdt.gen_proof()?);
+        Ok(())
+    }
+}
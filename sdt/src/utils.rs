@@ -1,8 +1,9 @@
 use rand::{thread_rng, RngCore};
 use serde::Serialize;
+use serde_json::{Map, Value};
 use sha2::Digest;
 
-use crate::error::SdtError;
+use crate::{cbor::Cbor, error::SdtError, jcs::to_jcs_string};
 
 pub(crate) fn create_random<const N: usize>() -> [u8; N] {
     let mut key_data = [0u8; N];
@@ -15,6 +16,14 @@ pub(crate) fn digest<T: Serialize>(payload: &T) -> Result<String, SdtError> {
     Ok(digest_str(&serde_json::to_string(payload)?))
 }
 
+pub(crate) fn digest_jcs<T: Serialize>(payload: &T) -> Result<String, SdtError> {
+    Ok(digest_str(&to_jcs_string(payload)?))
+}
+
+pub(crate) fn digest_cbor(payload: &Cbor) -> String {
+    to_hex_str(sha2::Sha256::digest(payload.encode()))
+}
+
 pub(crate) fn digest_str(payload: &str) -> String {
     to_hex_str(sha2::Sha256::digest(payload.as_bytes()))
 }
@@ -23,6 +32,41 @@ pub(crate) fn to_hex_str<T: AsRef<[u8]>>(data: T) -> String{
    format!("0x{}", hex::encode(data))
 }
 
+pub(crate) fn from_hex_str(s: &str) -> Result<Vec<u8>, SdtError> {
+    Ok(hex::decode(s.strip_prefix("0x").unwrap_or(s))?)
+}
+
+/// Serializes the payload as compact JSON with object keys sorted at every level,
+/// so the same document always produces the same bytes.
+///
+/// Integers are written as they are and floats in their shortest round-trip form,
+/// so `1.80` and `1.8` give the same bytes while `1.0` stays a float and keeps its proof.
+pub fn to_canonical_string<T: Serialize>(payload: &T) -> Result<String, SdtError> {
+    let value = canonicalize(serde_json::to_value(payload)?);
+    Ok(serde_json::to_string(&value)?)
+}
+
+/// Byte-level equality of the canonical serializations of two payloads.
+pub fn canonical_eq<T: Serialize>(left: &T, right: &T) -> Result<bool, SdtError> {
+    Ok(to_canonical_string(left)?.as_bytes() == to_canonical_string(right)?.as_bytes())
+}
+
+fn canonicalize(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            let mut sorted = Map::new();
+            for (k, v) in entries {
+                sorted.insert(k, canonicalize(v));
+            }
+            Value::Object(sorted)
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(canonicalize).collect()),
+        _ => value,
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
 struct QueryNode {
     parent: Option<Box<QueryNode>>,
@@ -30,30 +74,63 @@ struct QueryNode {
     children: Vec<QueryNode>,
 }
 
-pub fn parse_query(query: &str) -> Vec<String> {
+/// Paths of the keys of a query, e.g. `/personal/name/`. Every key and closing brace
+/// has to be on its own line.
+pub fn parse_query(query: &str) -> Result<Vec<String>, SdtError> {
     let mut query_keys: Vec<String> = vec![];
-    let lines: Vec<&str> = query.trim().split("\n").map(|x| x.trim()).collect();
     let mut node = QueryNode {
         parent: None,
         path: "".to_string(),
         children: vec![],
     };
-    for line in lines {
-        if line.ends_with("{") {
+    let mut last_line = 0;
+    for (index, raw_line) in query.split('\n').enumerate() {
+        let line = raw_line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        last_line = index + 1;
+        // columns count characters, keys and indentation aren't always ASCII
+        let indent = raw_line.len() - raw_line.trim_start().len();
+        let column = raw_line[..indent].chars().count() + 1;
+        let query_error = |message: &str, text: &str, offset: usize| SdtError::QueryError {
+            message: message.to_owned(),
+            path: node.path.to_owned(),
+            line: index + 1,
+            column: column + text[..offset].chars().count(),
+        };
+        if let Some(key) = line.strip_suffix('{') {
+            let key = key.trim();
+            if let Some(offset) = key.find(['{', '}']) {
+                return Err(query_error("Unexpected brace", key, offset));
+            }
             let new_node = QueryNode {
-                path: format!("{}{}/", node.path, line.replace("{", "").trim()),
+                path: format!("{}{}/", node.path, key),
                 parent: Some(Box::new(node.clone())),
                 children: vec![],
             };
             node.children.push(new_node.clone());
             node = new_node;
-        } else if line.trim() == "}" {
-            node = *node.parent.unwrap();
+        } else if line == "}" {
+            node = match node.parent {
+                Some(parent) => *parent,
+                None => return Err(query_error("Unmatched `}`", line, 0)),
+            };
+        } else if let Some(offset) = line.find(['{', '}']) {
+            return Err(query_error("Unexpected brace", line, offset));
         } else {
             query_keys.push(format!("{}{}/", node.path, line));
         }
     }
-    query_keys
+    if node.parent.is_some() {
+        return Err(SdtError::QueryError {
+            message: "Unclosed `{`".to_owned(),
+            path: node.path,
+            line: last_line,
+            column: 1,
+        });
+    }
+    Ok(query_keys)
 }
 
 
@@ -62,7 +139,7 @@ mod tests {
     use super::*;
  
     #[test]
-    fn parse_test() {
+    fn parse_test() -> Result<(), SdtError> {
         let query = "
             {
                 personal {
@@ -71,7 +148,60 @@ mod tests {
                 }
             }
             ";
-        let items = parse_query(query);
+        let items = parse_query(query)?;
         assert_eq!(items, vec!["/personal/name/", "/personal/surname/"]);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_query_test() {
+        let err = parse_query("{\n  personal {\n    name}\n  }\n}").unwrap_err();
+        assert!(matches!(
+            err,
+            SdtError::QueryError { ref path, line: 3, column: 9, .. } if path == "/personal/"
+        ));
+        let err = parse_query("{\n name\n}\n}").unwrap_err();
+        assert!(matches!(
+            err,
+            SdtError::QueryError {
+                line: 4,
+                column: 1,
+                ..
+            }
+        ));
+        let err = parse_query("{\n personal {\n name\n}").unwrap_err();
+        assert!(matches!(err, SdtError::QueryError { line: 4, .. }));
+        // columns count characters, not bytes
+        let err = parse_query("{\n\u{3000}çağ}ın\n}").unwrap_err();
+        assert!(matches!(
+            err,
+            SdtError::QueryError {
+                line: 2,
+                column: 5,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn canonical_string_test() -> Result<(), SdtError> {
+        let value: Value =
+            serde_json::from_str(r#"{"b": {"d": 1, "c": [{"f": true, "e": null}]}, "a": 1.5}"#)?;
+        assert_eq!(
+            to_canonical_string(&value)?,
+            r#"{"a":1.5,"b":{"c":[{"e":null,"f":true}],"d":1}}"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn canonical_number_test() -> Result<(), SdtError> {
+        let value: Value =
+            serde_json::from_str(r#"{"a": 1.80, "b": 1E2, "c": 1.0, "d": -5, "e": 0.1}"#)?;
+        let json = to_canonical_string(&value)?;
+        assert_eq!(json, r#"{"a":1.8,"b":100.0,"c":1.0,"d":-5,"e":0.1}"#);
+        let parsed: Value = serde_json::from_str(&json)?;
+        assert_eq!(to_canonical_string(&parsed)?, json);
+        Ok(())
     }
 }
@@ -1,9 +1,10 @@
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    cbor::Cbor,
     error::SdtError,
-    proof::SdtProof,
-    utils::{create_random, to_hex_str},
+    proof::{SdtHashSuite, SdtProof},
+    utils::{create_random, from_hex_str, to_hex_str},
 };
 use serde_json::Number;
 
@@ -28,11 +29,37 @@ impl SdtValue {
         Self { salt, value }
     }
 
+    /// Hashed with the serde_json suite as before versioned tries, `gen_proof_with` takes the suite.
     pub fn gen_proof(&self) -> Result<String, SdtError> {
+        self.gen_proof_with(SdtHashSuite::JsonSha256)
+    }
+
+    pub fn gen_proof_with(&self, suite: SdtHashSuite) -> Result<String, SdtError> {
         SdtProof::new()
-            .insert_str("salt", &self.salt)
+            .insert_hex("salt", &self.salt)
             .insert("value", self.value.clone())
-            .digest()
+            .digest_with(suite)
+    }
+
+    /// `[salt, value]` with the salt as raw bytes
+    pub fn to_cbor(&self) -> Result<Cbor, SdtError> {
+        Ok(Cbor::Array(vec![
+            Cbor::Bytes(from_hex_str(&self.salt)?),
+            self.value.to_cbor(),
+        ]))
+    }
+
+    pub fn from_cbor(item: &Cbor) -> Result<Self, SdtError> {
+        match item {
+            Cbor::Array(items) => match items.as_slice() {
+                [Cbor::Bytes(salt), value] => Ok(Self {
+                    salt: to_hex_str(salt),
+                    value: SdtValueKind::from_cbor(value)?,
+                }),
+                _ => Err(SdtError::CborError("Invalid value".to_owned())),
+            },
+            _ => Err(SdtError::CborError("Value should be an array".to_owned())),
+        }
     }
 }
 
@@ -40,6 +67,41 @@ impl SdtValueKind {
     pub fn new_i64(number: i64) -> Self {
         SdtValueKind::Number(Number::from(number))
     }
+
+    pub fn to_cbor(&self) -> Cbor {
+        match self {
+            Self::Null => Cbor::Null,
+            Self::Bool(b) => Cbor::Bool(*b),
+            Self::String(s) => Cbor::Text(s.to_owned()),
+            Self::Number(n) => {
+                if let Some(u) = n.as_u64() {
+                    Cbor::Unsigned(u)
+                } else if let Some(i) = n.as_i64() {
+                    Cbor::Negative((-1 - i) as u64)
+                } else {
+                    Cbor::Float(n.as_f64().unwrap_or_default())
+                }
+            }
+        }
+    }
+
+    pub fn from_cbor(item: &Cbor) -> Result<Self, SdtError> {
+        let value = match item {
+            Cbor::Null => Self::Null,
+            Cbor::Bool(b) => Self::Bool(*b),
+            Cbor::Text(s) => Self::String(s.to_owned()),
+            Cbor::Unsigned(u) => Self::Number(Number::from(*u)),
+            Cbor::Negative(n) if *n <= i64::MAX as u64 => {
+                Self::Number(Number::from(-1 - *n as i64))
+            }
+            Cbor::Float(f) => Self::Number(
+                Number::from_f64(*f)
+                    .ok_or_else(|| SdtError::CborError(format!("Invalid number {f}")))?,
+            ),
+            _ => return Err(SdtError::CborError("Invalid value kind".to_owned())),
+        };
+        Ok(value)
+    }
 }
 
 #[cfg(test)]
@@ -58,4 +120,53 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn gen_proof_suite_test() -> Result<(), SdtError> {
+        let val = SdtValue {
+            salt: "0x1234567890".to_owned(),
+            value: SdtValueKind::Number(serde_json::from_str("1.0")?),
+        };
+        assert_ne!(
+            val.gen_proof_with(SdtHashSuite::JsonSha256)?,
+            val.gen_proof_with(SdtHashSuite::JcsSha256)?
+        );
+        let normalized = SdtValue {
+            value: SdtValueKind::new_i64(1),
+            ..val.clone()
+        };
+        assert_eq!(
+            val.gen_proof_with(SdtHashSuite::JcsSha256)?,
+            normalized.gen_proof_with(SdtHashSuite::JcsSha256)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn cbor_test() -> Result<(), SdtError> {
+        let values = vec![
+            SdtValueKind::Null,
+            SdtValueKind::Bool(true),
+            SdtValueKind::new_i64(-500),
+            SdtValueKind::new_i64(2020),
+            SdtValueKind::Number(serde_json::from_str("1.5")?),
+            SdtValueKind::String("Çağlın".to_owned()),
+        ];
+        for value in values {
+            let val = SdtValue::new(value);
+            let encoded = val.to_cbor()?.encode();
+            assert_eq!(SdtValue::from_cbor(&Cbor::decode(&encoded)?)?, val);
+        }
+        let val = SdtValue {
+            salt: "0x1234567890".to_owned(),
+            value: SdtValueKind::Null,
+        };
+        assert_eq!(hex::encode(val.to_cbor()?.encode()), "82451234567890f6");
+        // sha256 of {"salt": h'1234567890', "value": null}
+        assert_eq!(
+            val.gen_proof_with(SdtHashSuite::CborSha256)?,
+            "0xc5912172a263b163abc2ece53d8709f821a24c398f83da5abe423209c2d82b60"
+        );
+        Ok(())
+    }
 }
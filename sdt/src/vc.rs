@@ -0,0 +1,675 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{de, de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    anchor::Anchor,
+    did::{Did, DidResolver, DidVerifier},
+    error::SdtError,
+    jcs::to_jcs_string,
+    signer::{SdtSigner, SdtVerifier, ED25519_ALGORITHM},
+    status::{self, StatusFetcher},
+    Sdt,
+};
+
+pub const CREDENTIALS_V2_CONTEXT: &str = "https://www.w3.org/ns/credentials/v2";
+const CREDENTIAL_TYPE: &str = "VerifiableCredential";
+const SDT_CREDENTIAL_TYPE: &str = "SdtCredential";
+const PRESENTATION_TYPE: &str = "VerifiablePresentation";
+const PROOF_TYPE: &str = "DataIntegrityProof";
+const EDDSA_JCS_CRYPTOSUITE: &str = "eddsa-jcs-2022";
+const SDT_VOCAB: &str = "urn:sdt:";
+
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SdtCredentialSubject {
+    /// Subject of the trie
+    pub id: String,
+    /// `Sdt::gen_proof` of the trie
+    pub sdt_root: String,
+    pub sdt_version: u64,
+}
+
+/// Data Integrity proof with the `eddsa-jcs-2022` cryptosuite
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SdtDataIntegrityProof {
+    #[serde(rename = "type")]
+    pub proof_type: String,
+    pub cryptosuite: String,
+    pub created: String,
+    pub verification_method: String,
+    pub proof_purpose: String,
+    pub proof_value: String,
+}
+
+/// A VC Data Model 2.0 credential that commits to an `Sdt` root.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[serde(remote = "Self", rename_all = "camelCase")]
+pub struct SdtCredential {
+    #[serde(rename = "@context")]
+    pub context: Vec<Value>,
+    #[serde(rename = "type")]
+    pub types: Vec<String>,
+    pub issuer: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_from: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_until: Option<DateTime<Utc>>,
+    pub credential_subject: SdtCredentialSubject,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proof: Option<SdtDataIntegrityProof>,
+    #[serde(skip)]
+    source: Source,
+}
+
+/// A VC Data Model 2.0 presentation carrying credentials and the selected tries they commit to.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[serde(remote = "Self", rename_all = "camelCase")]
+pub struct SdtPresentation {
+    #[serde(rename = "@context")]
+    pub context: Vec<Value>,
+    #[serde(rename = "type")]
+    pub types: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub holder: Option<String>,
+    pub verifiable_credential: Vec<SdtCredential>,
+    /// Selected tries, in the same order as the credentials
+    pub sdt: Vec<Sdt>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proof: Option<SdtDataIntegrityProof>,
+    #[serde(skip)]
+    source: Source,
+}
+
+/// The JSON a document was parsed from, it isn't part of equality
+#[derive(Debug, Clone, Default)]
+struct Source(Option<Value>);
+
+impl PartialEq for Source {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl SdtCredential {
+    pub fn new(issuer: &str, sdt: &Sdt) -> Result<Self, SdtError> {
+        Ok(Self {
+            context: vec![json!(CREDENTIALS_V2_CONTEXT), sdt_context()],
+            types: vec![CREDENTIAL_TYPE.to_owned(), SDT_CREDENTIAL_TYPE.to_owned()],
+            issuer: issuer.to_owned(),
+            valid_from: None,
+            valid_until: None,
+            credential_subject: SdtCredentialSubject {
+                id: sdt.subject.to_owned(),
+                sdt_root: sdt.gen_proof()?,
+                sdt_version: sdt.version,
+            },
+            proof: None,
+            source: Source::default(),
+        })
+    }
+
+    pub fn set_valid_from(&mut self, valid_from: DateTime<Utc>) -> &mut Self {
+        self.valid_from = Some(valid_from);
+        self
+    }
+
+    pub fn set_valid_until(&mut self, valid_until: DateTime<Utc>) -> &mut Self {
+        self.valid_until = Some(valid_until);
+        self
+    }
+
+    pub fn build(&self) -> Self {
+        self.to_owned()
+    }
+
+    /// Adds an `assertionMethod` proof, the signer key id is the verification method.
+    pub fn sign(
+        &mut self,
+        signer: &dyn SdtSigner,
+        created: DateTime<Utc>,
+    ) -> Result<&mut Self, SdtError> {
+        self.proof = None;
+        let document = serde_json::to_value(&*self)?;
+        self.proof = Some(create_proof(&document, signer, "assertionMethod", created)?);
+        Ok(self)
+    }
+
+    /// Checks the shape, the validity period at `now` and the issuer proof.
+    pub fn verify(&self, verifier: &dyn SdtVerifier, now: DateTime<Utc>) -> Result<(), SdtError> {
+        check_context(&self.context)?;
+        if !self.types.iter().any(|t| t == CREDENTIAL_TYPE) {
+            return Err(SdtError::CredentialError(format!(
+                "Type should contain {CREDENTIAL_TYPE}"
+            )));
+        }
+        if let Some(valid_from) = self.valid_from {
+            if now < valid_from {
+                return Err(SdtError::CredentialError(
+                    "Credential is not valid yet".to_owned(),
+                ));
+            }
+        }
+        if let Some(valid_until) = self.valid_until {
+            if now > valid_until {
+                return Err(SdtError::CredentialError(
+                    "Credential is expired".to_owned(),
+                ));
+            }
+        }
+        let proof = self
+            .proof
+            .as_ref()
+            .ok_or_else(|| SdtError::CredentialError("Credential has no proof".to_owned()))?;
+        verify_proof(
+            &unsecured_document(self, &self.source)?,
+            proof,
+            "assertionMethod",
+            verifier,
+        )
+    }
+
+    /// Checks that a (selected) trie belongs to the credential.
+    pub fn verify_sdt(&self, sdt: &Sdt) -> Result<bool, SdtError> {
+        if sdt.subject != self.credential_subject.id
+            || sdt.version != self.credential_subject.sdt_version
+        {
+            return Err(SdtError::CredentialError(
+                "Trie doesn't match the credential subject".to_owned(),
+            ));
+        }
+        sdt.verify(&self.credential_subject.sdt_root)
+    }
+
+    /// Checks that the root of the credential is the latest anchored root of the subject,
+    /// credentials of replaced versions are rejected.
+    pub fn verify_anchor(&self, anchor: &dyn Anchor) -> Result<(), SdtError> {
+        let subject = &self.credential_subject;
+        let latest = anchor
+            .latest(&subject.id)?
+            .ok_or_else(|| SdtError::UnknownSubject(subject.id.to_owned()))?;
+        if latest.root != subject.sdt_root {
+            return Err(SdtError::AnchorError(format!(
+                "Latest root of {} is {}",
+                subject.id, latest.root
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl SdtPresentation {
+    pub fn new() -> Self {
+        Self {
+            context: vec![json!(CREDENTIALS_V2_CONTEXT), sdt_context()],
+            types: vec![PRESENTATION_TYPE.to_owned()],
+            holder: None,
+            verifiable_credential: vec![],
+            sdt: vec![],
+            proof: None,
+            source: Source::default(),
+        }
+    }
+
+    pub fn set_holder(&mut self, holder: &str) -> &mut Self {
+        self.holder = Some(holder.to_owned());
+        self
+    }
+
+    /// Adds a credential with the selected trie that is presented for it.
+    pub fn add_credential(&mut self, credential: SdtCredential, sdt: Sdt) -> &mut Self {
+        self.verifiable_credential.push(credential);
+        self.sdt.push(sdt);
+        self
+    }
+
+    pub fn build(&self) -> Self {
+        self.to_owned()
+    }
+
+    /// Adds an `authentication` proof of the holder.
+    pub fn sign(
+        &mut self,
+        signer: &dyn SdtSigner,
+        created: DateTime<Utc>,
+    ) -> Result<&mut Self, SdtError> {
+        self.proof = None;
+        let document = serde_json::to_value(&*self)?;
+        self.proof = Some(create_proof(&document, signer, "authentication", created)?);
+        Ok(self)
+    }
+
+    /// Verifies every credential with the issuer verifier, the tries against their credentials
+    /// and, when a holder verifier is given, the holder proof.
+    pub fn verify(
+        &self,
+        issuer_verifier: &dyn SdtVerifier,
+        holder_verifier: Option<&dyn SdtVerifier>,
+        now: DateTime<Utc>,
+    ) -> Result<(), SdtError> {
+        check_context(&self.context)?;
+        if !self.types.iter().any(|t| t == PRESENTATION_TYPE) {
+            return Err(SdtError::CredentialError(format!(
+                "Type should contain {PRESENTATION_TYPE}"
+            )));
+        }
+        if self.verifiable_credential.len() != self.sdt.len() {
+            return Err(SdtError::CredentialError(
+                "Each credential should have a trie".to_owned(),
+            ));
+        }
+        for (credential, sdt) in self.verifiable_credential.iter().zip(&self.sdt) {
+            credential.verify(issuer_verifier, now)?;
+            credential.verify_sdt(sdt)?;
+        }
+        if let Some(holder_verifier) = holder_verifier {
+            let proof = self
+                .proof
+                .as_ref()
+                .ok_or_else(|| SdtError::CredentialError("Presentation has no proof".to_owned()))?;
+            verify_proof(
+                &unsecured_document(self, &self.source)?,
+                proof,
+                "authentication",
+                holder_verifier,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Checks that no trie is revoked or suspended, their `status` entries have to be disclosed
+    pub fn verify_status(&self, fetcher: &dyn StatusFetcher) -> Result<(), SdtError> {
+        for sdt in &self.sdt {
+            status::verify(sdt, fetcher)?;
+        }
+        Ok(())
+    }
+
+    /// Verifies with keys resolved from DIDs: credential proofs need a key of their issuer,
+    /// the presentation proof a key of the holder, who has to be the subject of every trie.
+    pub fn verify_with_resolver(
+        &self,
+        resolver: &dyn DidResolver,
+        now: DateTime<Utc>,
+    ) -> Result<(), SdtError> {
+        let holder = self
+            .holder
+            .as_deref()
+            .ok_or_else(|| SdtError::CredentialError("Presentation has no holder".to_owned()))?;
+        if self.sdt.iter().any(|sdt| sdt.subject != holder) {
+            return Err(SdtError::CredentialError(format!(
+                "{holder} is not the subject of every trie"
+            )));
+        }
+        for credential in &self.verifiable_credential {
+            let issuer = Did::parse(&credential.issuer)?;
+            let signed_by_issuer = credential.proof.as_ref().is_some_and(|proof| {
+                Did::parse_url(&proof.verification_method).is_ok_and(|(did, _)| did == issuer)
+            });
+            if !signed_by_issuer {
+                return Err(SdtError::CredentialError(format!(
+                    "Credential is not signed by {issuer}"
+                )));
+            }
+        }
+        let holder_verifier = DidVerifier::new(resolver).with_controller(holder)?;
+        self.verify(&DidVerifier::new(resolver), Some(&holder_verifier), now)
+    }
+}
+
+impl Default for SdtPresentation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Serde impls that keep the parsed JSON, see `unsecured_document`
+macro_rules! impl_serde_with_source {
+    ($document:ident) => {
+        impl Serialize for $document {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                $document::serialize(self, serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $document {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let source = Value::deserialize(deserializer)?;
+                let mut document = $document::deserialize(&source).map_err(de::Error::custom)?;
+                document.source = Source(Some(source));
+                Ok(document)
+            }
+        }
+    };
+}
+
+impl_serde_with_source!(SdtCredential);
+impl_serde_with_source!(SdtPresentation);
+
+/// Inline context for the terms of this crate, tries are kept as JSON literals
+fn sdt_context() -> Value {
+    json!({
+        "@protected": true,
+        "SdtCredential": format!("{SDT_VOCAB}SdtCredential"),
+        "sdtRoot": format!("{SDT_VOCAB}sdtRoot"),
+        "sdtVersion": format!("{SDT_VOCAB}sdtVersion"),
+        "sdt": {"@id": format!("{SDT_VOCAB}sdt"), "@type": "@json"},
+    })
+}
+
+/// The document without its proof. A parsed document is hashed as it was received, so
+/// members this crate doesn't know and its own formatting of values are kept, unless its
+/// fields were changed since.
+fn unsecured_document<T>(document: &T, source: &Source) -> Result<Value, SdtError>
+where
+    T: Serialize + DeserializeOwned + PartialEq,
+{
+    let received = source.0.as_ref().filter(|source| {
+        serde_json::from_value::<T>((*source).clone()).is_ok_and(|parsed| parsed == *document)
+    });
+    let mut unsecured = match received {
+        Some(source) => source.clone(),
+        None => serde_json::to_value(document)?,
+    };
+    if let Value::Object(map) = &mut unsecured {
+        map.remove("proof");
+    }
+    Ok(unsecured)
+}
+
+fn check_context(context: &[Value]) -> Result<(), SdtError> {
+    if context.first().and_then(|c| c.as_str()) != Some(CREDENTIALS_V2_CONTEXT) {
+        return Err(SdtError::CredentialError(format!(
+            "First context should be {CREDENTIALS_V2_CONTEXT}"
+        )));
+    }
+    Ok(())
+}
+
+/// `sha256(jcs(proof config)) || sha256(jcs(document))` as in `eddsa-jcs-2022`
+fn hash_data(document: &Value, proof: &SdtDataIntegrityProof) -> Result<Vec<u8>, SdtError> {
+    let mut config = serde_json::to_value(proof)?;
+    if let Value::Object(map) = &mut config {
+        map.remove("proofValue");
+        if let Some(context) = document.get("@context") {
+            map.insert("@context".to_owned(), context.to_owned());
+        }
+    }
+    let mut data = Sha256::digest(to_jcs_string(&config)?.as_bytes()).to_vec();
+    data.extend_from_slice(&Sha256::digest(to_jcs_string(document)?.as_bytes()));
+    Ok(data)
+}
+
+fn create_proof(
+    document: &Value,
+    signer: &dyn SdtSigner,
+    purpose: &str,
+    created: DateTime<Utc>,
+) -> Result<SdtDataIntegrityProof, SdtError> {
+    if signer.algorithm() != ED25519_ALGORITHM {
+        return Err(SdtError::CredentialError(format!(
+            "Unsupported algorithm {}",
+            signer.algorithm()
+        )));
+    }
+    let verification_method = signer
+        .key_id()
+        .ok_or_else(|| SdtError::CredentialError("Signer should have a key id".to_owned()))?;
+    let mut proof = SdtDataIntegrityProof {
+        proof_type: PROOF_TYPE.to_owned(),
+        cryptosuite: EDDSA_JCS_CRYPTOSUITE.to_owned(),
+        created: created.to_rfc3339_opts(SecondsFormat::Secs, true),
+        verification_method,
+        proof_purpose: purpose.to_owned(),
+        proof_value: String::new(),
+    };
+    let sig = signer.sign(&hash_data(document, &proof)?)?;
+    proof.proof_value = format!("z{}", bs58::encode(sig).into_string());
+    Ok(proof)
+}
+
+fn verify_proof(
+    document: &Value,
+    proof: &SdtDataIntegrityProof,
+    purpose: &str,
+    verifier: &dyn SdtVerifier,
+) -> Result<(), SdtError> {
+    if proof.proof_type != PROOF_TYPE || proof.cryptosuite != EDDSA_JCS_CRYPTOSUITE {
+        return Err(SdtError::CredentialError(format!(
+            "Unsupported proof {} {}",
+            proof.proof_type, proof.cryptosuite
+        )));
+    }
+    if proof.proof_purpose != purpose {
+        return Err(SdtError::CredentialError(format!(
+            "Proof purpose should be {purpose}"
+        )));
+    }
+    let sig = proof
+        .proof_value
+        .strip_prefix('z')
+        .and_then(|v| bs58::decode(v).into_vec().ok())
+        .ok_or_else(|| SdtError::CredentialError("Invalid proof value".to_owned()))?;
+    verifier.verify(
+        ED25519_ALGORITHM,
+        Some(&proof.verification_method),
+        &hash_data(document, proof)?,
+        &sig,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone};
+
+    use super::*;
+    use crate::{
+        anchor::FileAnchorLog,
+        did::{DidDocument, MemoryDidResolver, MethodResolver, VerificationMethod},
+        node::{SdtClaim, SdtNode},
+        signer::Ed25519Signer,
+        status::{FileStatusStore, StatusEntry, StatusList, StatusPurpose},
+    };
+
+    fn create_sdt() -> Result<Sdt, SdtError> {
+        let claim: SdtClaim = serde_json::from_str(
+            r#"{"personal": {"name": "Adem", "surname": "Çağlın", "age_over_18": true}}"#,
+        )?;
+        Ok(Sdt::new("did:p2p:123456", claim.to_node()))
+    }
+
+    #[test]
+    fn credential_test() -> Result<(), SdtError> {
+        let sdt = create_sdt()?;
+        let issuer = Ed25519Signer::new().with_key_id("did:p2p:issuer#key-1");
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let credential = SdtCredential::new("did:p2p:issuer", &sdt)?
+            .set_valid_from(now)
+            .set_valid_until(now + Duration::days(365))
+            .sign(&issuer, now)?
+            .build();
+        let json = serde_json::to_string(&credential)?;
+        assert!(json.contains(r#""validFrom":"2024-05-01T12:00:00Z""#));
+        assert!(json.contains(r#""cryptosuite":"eddsa-jcs-2022""#));
+        assert!(json.contains(r#""sdtRoot":"urn:sdt:sdtRoot""#));
+        let parsed: SdtCredential = serde_json::from_str(&json)?;
+        assert_eq!(parsed, credential);
+        parsed.verify(&issuer.verifier(), now + Duration::days(1))?;
+        assert!(parsed
+            .verify(&issuer.verifier(), now - Duration::days(1))
+            .is_err());
+        assert!(parsed
+            .verify(&issuer.verifier(), now + Duration::days(366))
+            .is_err());
+        assert!(parsed
+            .verify(&Ed25519Signer::new().verifier(), now)
+            .is_err());
+
+        let mut tampered = parsed.clone();
+        tampered.credential_subject.id = "did:p2p:other".to_owned();
+        assert!(tampered.verify(&issuer.verifier(), now).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn received_document_test() -> Result<(), SdtError> {
+        let sdt = create_sdt()?;
+        let issuer = Ed25519Signer::new().with_key_id("did:p2p:issuer#key-1");
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        // an issuer adding its own members and writing timestamps with milliseconds
+        let mut document = serde_json::to_value(SdtCredential::new("did:p2p:issuer", &sdt)?)?;
+        document["name"] = json!("Diploma");
+        document["validFrom"] = json!("2024-05-01T12:00:00.000Z");
+        let proof = create_proof(&document, &issuer, "assertionMethod", now)?;
+        document["proof"] = serde_json::to_value(proof)?;
+        let credential: SdtCredential = serde_json::from_value(document)?;
+        credential.verify(&issuer.verifier(), now)?;
+
+        let mut tampered = credential.clone();
+        tampered.credential_subject.sdt_root =
+            tampered.credential_subject.sdt_root.replace("0x", "0x0");
+        assert!(tampered.verify(&issuer.verifier(), now).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn anchor_test() -> Result<(), SdtError> {
+        let dir = tempfile::tempdir()?;
+        let log = FileAnchorLog::new(dir.path().join("anchors.jsonl"));
+        let mut sdt = create_sdt()?;
+        let credential = SdtCredential::new("did:p2p:issuer", &sdt)?;
+        assert!(credential.verify_anchor(&log).is_err());
+        log.publish_sdt(&sdt)?;
+        credential.verify_anchor(&log)?;
+
+        sdt.mutate(SdtNode::new());
+        log.publish_sdt(&sdt)?;
+        assert!(matches!(
+            credential.verify_anchor(&log),
+            Err(SdtError::AnchorError(_))
+        ));
+        SdtCredential::new("did:p2p:issuer", &sdt)?.verify_anchor(&log)?;
+        Ok(())
+    }
+
+    #[test]
+    fn resolver_test() -> Result<(), SdtError> {
+        let issuer_key = Ed25519Signer::new();
+        let issuer_did = Did::from_ed25519(&issuer_key.public_key()).to_string();
+        let issuer = issuer_key.with_key_id(&format!(
+            "{issuer_did}#{}",
+            issuer_did.trim_start_matches("did:key:")
+        ));
+        let holder = Ed25519Signer::new().with_key_id("did:p2p:123456#key-1");
+        let mut p2p = MemoryDidResolver::new();
+        let holder_did = Did::parse("did:p2p:123456")?;
+        p2p.insert(DidDocument {
+            id: holder_did.to_string(),
+            verification_method: vec![VerificationMethod::ed25519(
+                &holder_did,
+                "key-1",
+                &holder.public_key(),
+            )],
+        });
+        let resolver = MethodResolver::new().with_method("p2p", Box::new(p2p));
+
+        let sdt = create_sdt()?;
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let credential = SdtCredential::new(&issuer_did, &sdt)?
+            .sign(&issuer, now)?
+            .build();
+        let presentation = SdtPresentation::new()
+            .set_holder("did:p2p:123456")
+            .add_credential(credential.clone(), sdt.clone())
+            .sign(&holder, now)?
+            .build();
+        presentation.verify_with_resolver(&resolver, now)?;
+
+        // a presentation signed by someone else than the subject
+        let other = Ed25519Signer::new().with_key_id("did:p2p:123456#key-1");
+        let forged = SdtPresentation::new()
+            .set_holder("did:p2p:123456")
+            .add_credential(credential.clone(), sdt.clone())
+            .sign(&other, now)?
+            .build();
+        assert!(forged.verify_with_resolver(&resolver, now).is_err());
+        let mut unbound = presentation.clone();
+        unbound.holder = Some("did:p2p:654321".to_owned());
+        assert!(unbound.verify_with_resolver(&resolver, now).is_err());
+
+        // a credential claiming an issuer it wasn't signed by
+        let mut credential = SdtCredential::new("did:p2p:issuer", &sdt)?;
+        credential.sign(&issuer, now)?;
+        let presentation = SdtPresentation::new()
+            .set_holder("did:p2p:123456")
+            .add_credential(credential, sdt)
+            .sign(&holder, now)?
+            .build();
+        assert!(matches!(
+            presentation.verify_with_resolver(&resolver, now),
+            Err(SdtError::CredentialError(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn presentation_test() -> Result<(), SdtError> {
+        let sdt = create_sdt()?;
+        let issuer = Ed25519Signer::new().with_key_id("did:p2p:issuer#key-1");
+        let holder = Ed25519Signer::new().with_key_id("did:p2p:123456#key-1");
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let credential = SdtCredential::new("did:p2p:issuer", &sdt)?
+            .sign(&issuer, now)?
+            .build();
+        let selected = sdt.select("{\n personal {\n age_over_18\n }\n}")?;
+        let presentation = SdtPresentation::new()
+            .set_holder("did:p2p:123456")
+            .add_credential(credential.clone(), selected)
+            .sign(&holder, now)?
+            .build();
+        let parsed: SdtPresentation = serde_json::from_str(&serde_json::to_string(&presentation)?)?;
+        parsed.verify(&issuer.verifier(), Some(&holder.verifier()), now)?;
+        assert!(parsed
+            .verify(&issuer.verifier(), Some(&issuer.verifier()), now)
+            .is_err());
+
+        let other = create_sdt()?;
+        let wrong = SdtPresentation::new()
+            .add_credential(credential, other)
+            .build();
+        assert!(wrong.verify(&issuer.verifier(), None, now).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn status_test() -> Result<(), SdtError> {
+        const LIST: &str = "https://example.com/status/1";
+        let dir = tempfile::tempdir()?;
+        let store = FileStatusStore::new(dir.path())?;
+        let entry = StatusEntry::new(LIST, 7, StatusPurpose::Revocation);
+        let mut root = create_sdt()?.inception.node;
+        entry.add_to(&mut root);
+        let sdt = Sdt::new("did:p2p:123456", root);
+        let credential = SdtCredential::new("did:p2p:issuer", &sdt)?;
+        let presentation = SdtPresentation::new()
+            .add_credential(
+                credential,
+                sdt.select("{\n personal {\n name\n }\n status\n}")?,
+            )
+            .build();
+
+        let mut list = StatusList::new(16);
+        list.set(8, true)?;
+        store.publish(LIST, &list, entry.purpose)?;
+        presentation.verify_status(&store)?;
+        list.set(7, true)?;
+        store.publish(LIST, &list, entry.purpose)?;
+        assert!(matches!(
+            presentation.verify_status(&store),
+            Err(SdtError::Revoked { index: 7, .. })
+        ));
+        Ok(())
+    }
+}
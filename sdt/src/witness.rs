@@ -0,0 +1,230 @@
+//! Receipts of witnesses over chain heads, in the style of KERI witnesses.
+//!
+//! After a mutation the holder collects receipts over the new head from its witnesses. A
+//! witness remembers the chains it signed and refuses heads that fork from them, so a
+//! holder can't show different histories to different verifiers without the witnesses
+//! noticing. Verifiers require a threshold of valid receipts from witnesses they trust.
+
+use std::{collections::HashMap, sync::Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth::{ed25519_key, verify_signature, SdtSignature},
+    error::SdtError,
+    signer::{Ed25519Signer, SdtSigner},
+    utils::{from_hex_str, to_hex_str},
+    Sdt,
+};
+
+/// Signature of a witness over the chain proof after an item, `key_id` is the witness key
+pub type SdtReceipt = SdtSignature;
+
+pub trait Witness {
+    /// Ed25519 key of the witness, see `auth::ed25519_key`
+    fn id(&self) -> String;
+    /// Signs the head of the trie, refuses heads that fork from a chain it witnessed
+    fn receipt(&self, sdt: &Sdt) -> Result<SdtReceipt, SdtError>;
+}
+
+/// A witness running in the process, it keeps the proofs of every chain it signed in memory
+pub struct LocalWitness {
+    signer: Ed25519Signer,
+    chains: Mutex<HashMap<String, Vec<String>>>,
+}
+
+impl LocalWitness {
+    pub fn new(signer: Ed25519Signer) -> Self {
+        LocalWitness {
+            signer,
+            chains: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl Default for LocalWitness {
+    fn default() -> Self {
+        Self::new(Ed25519Signer::new())
+    }
+}
+
+impl Witness for LocalWitness {
+    fn id(&self) -> String {
+        ed25519_key(&self.signer.public_key())
+    }
+
+    fn receipt(&self, sdt: &Sdt) -> Result<SdtReceipt, SdtError> {
+        let history = sdt.history()?;
+        let head = &history[history.len() - 1];
+        // only authorized mutations are witnessed
+        sdt.verify(head)?;
+        let mut chains = self.chains.lock().unwrap_or_else(|e| e.into_inner());
+        let chain = chains.entry(sdt.subject.to_owned()).or_default();
+        if chain
+            .iter()
+            .zip(&history)
+            .any(|(seen, proof)| seen != proof)
+        {
+            return Err(SdtError::WitnessError(format!(
+                "{} forks from a witnessed chain",
+                sdt.subject
+            )));
+        }
+        if history.len() > chain.len() {
+            *chain = history.clone();
+        }
+        Ok(SdtReceipt {
+            key_id: self.id(),
+            signature: to_hex_str(self.signer.sign(&from_hex_str(head)?)?),
+        })
+    }
+}
+
+/// Witnesses a verifier trusts and how many of them must have signed a head
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+pub struct WitnessPolicy {
+    pub witnesses: Vec<String>,
+    pub threshold: usize,
+}
+
+impl WitnessPolicy {
+    pub fn new(witnesses: Vec<String>, threshold: usize) -> Result<Self, SdtError> {
+        if threshold == 0 || threshold > witnesses.len() {
+            return Err(SdtError::WitnessError(format!(
+                "Threshold {threshold} can't be met by {} witnesses",
+                witnesses.len()
+            )));
+        }
+        Ok(WitnessPolicy {
+            witnesses,
+            threshold,
+        })
+    }
+
+    /// Checks the receipts of the head, returns the number of valid receipts
+    pub fn verify(&self, sdt: &Sdt) -> Result<usize, SdtError> {
+        self.verify_at(sdt, sdt.items().len() - 1)
+    }
+
+    /// Checks the receipts of the item at `position` against the chain proof after it
+    pub fn verify_at(&self, sdt: &Sdt, position: usize) -> Result<usize, SdtError> {
+        let items = sdt.items();
+        let item = items.get(position).ok_or(SdtError::PositionError {
+            position,
+            len: items.len(),
+        })?;
+        let msg = from_hex_str(&sdt.history()?[position])?;
+        let mut signed: Vec<&str> = vec![];
+        for receipt in &item.receipts {
+            let witness = receipt.key_id.as_str();
+            if self.witnesses.iter().any(|w| w == witness)
+                && !signed.contains(&witness)
+                && verify_signature(witness, &msg, &receipt.signature).is_ok()
+            {
+                signed.push(witness);
+            }
+        }
+        if signed.len() < self.threshold {
+            return Err(SdtError::WitnessError(format!(
+                "Item {position} has {} of {} receipts",
+                signed.len(),
+                self.threshold
+            )));
+        }
+        Ok(signed.len())
+    }
+}
+
+/// Asks every witness for a receipt of the head and keeps them on the head item, returns
+/// the number of receipts the head has.
+///
+/// Witnesses that refuse are skipped, nothing is kept when fewer than `threshold` sign.
+pub fn collect(
+    sdt: &mut Sdt,
+    witnesses: &[&dyn Witness],
+    threshold: usize,
+) -> Result<usize, SdtError> {
+    let receipts: Vec<SdtReceipt> = witnesses
+        .iter()
+        .filter_map(|witness| witness.receipt(sdt).ok())
+        .collect();
+    if receipts.len() < threshold {
+        return Err(SdtError::WitnessError(format!(
+            "{} of {threshold} witnesses signed the head of {}",
+            receipts.len(),
+            sdt.subject
+        )));
+    }
+    let head = sdt.inception.find_current();
+    for receipt in receipts {
+        head.receipts.retain(|r| r.key_id != receipt.key_id);
+        head.receipts.push(receipt);
+    }
+    Ok(head.receipts.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{compact, node::SdtNode};
+
+    use super::*;
+
+    fn create_sdt() -> Sdt {
+        Sdt::new(
+            "did:p2p:123456",
+            SdtNode::new().add_str_value("name", "Adem").build(),
+        )
+        .mutate(SdtNode::new().add_str_value("name", "Ahmet").build())
+        .build()
+    }
+
+    #[test]
+    fn collect_verify_test() -> Result<(), SdtError> {
+        let witnesses = [
+            LocalWitness::default(),
+            LocalWitness::default(),
+            LocalWitness::default(),
+        ];
+        let refs: Vec<&dyn Witness> = witnesses.iter().map(|w| w as &dyn Witness).collect();
+        let policy = WitnessPolicy::new(witnesses.iter().map(|w| w.id()).collect(), 2)?;
+        let mut sdt = create_sdt();
+        assert_eq!(collect(&mut sdt, &refs, 2)?, 3);
+        assert_eq!(collect(&mut sdt, &refs, 2)?, 3);
+        assert_eq!(policy.verify(&sdt)?, 3);
+        let decoded = compact::from_compact_bytes(&compact::to_compact_bytes(&sdt)?)?;
+        assert_eq!(policy.verify(&decoded)?, 3);
+        assert!(matches!(
+            policy.verify_at(&sdt, 0),
+            Err(SdtError::WitnessError(_))
+        ));
+
+        let untrusted = WitnessPolicy::new(vec![LocalWitness::default().id()], 1)?;
+        assert!(untrusted.verify(&sdt).is_err());
+        let mut tampered = sdt.clone();
+        tampered.inception.find_current().node =
+            SdtNode::new().add_str_value("name", "Mehmet").build();
+        assert!(policy.verify(&tampered).is_err());
+        assert!(WitnessPolicy::new(vec![], 1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn fork_test() -> Result<(), SdtError> {
+        let witness = LocalWitness::default();
+        let mut sdt = create_sdt();
+        let mut fork = sdt.clone();
+        collect(&mut sdt, &[&witness], 1)?;
+        sdt.mutate(SdtNode::new().add_str_value("name", "Mehmet").build());
+        collect(&mut sdt, &[&witness], 1)?;
+
+        fork.mutate(SdtNode::new().add_str_value("name", "Ali").build());
+        assert!(matches!(
+            collect(&mut fork, &[&witness], 1),
+            Err(SdtError::WitnessError(_))
+        ));
+        assert!(fork.inception.find_current().receipts.is_empty());
+        // older heads of the same chain can still be receipted
+        assert!(witness.receipt(&sdt.truncate(0)?).is_ok());
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+//! Size regression tests of the compact presentation format.
+//!
+//! Salts are derived deterministically so the encoded sizes are stable, a change in
+//! the format that makes the presentations larger should fail here first.
+
+use sdt::{
+    compact::{self, SdtArmor},
+    error::SdtError,
+    node::{SdtClaim, SdtNode},
+    value::SdtValue,
+    Sdt,
+};
+use sha2::{Digest, Sha256};
+
+fn to_node(claim: &SdtClaim, path: &str) -> SdtNode {
+    let mut node = SdtNode::new();
+    if let SdtClaim::Node(map) = claim {
+        for (k, v) in map {
+            let path = format!("{path}/{k}");
+            match v {
+                SdtClaim::Value(value) => {
+                    let salt = Sha256::digest(path.as_bytes());
+                    node.add_sdt_value(
+                        k,
+                        SdtValue {
+                            salt: format!("0x{}", hex::encode(&salt[..16])),
+                            value: value.to_owned(),
+                        },
+                    );
+                }
+                SdtClaim::Node(_) => {
+                    node.add_node(k, to_node(v, &path));
+                }
+            }
+        }
+    }
+    node
+}
+
+fn create_sdt() -> Result<Sdt, SdtError> {
+    let inception: SdtClaim = serde_json::from_str(
+        r#"{
+            "personal": {
+                "name": "Adem",
+                "surname": "Çağlın",
+                "birthdate": "1990-01-01",
+                "age_over_18": true,
+                "nationality": "TR"
+            },
+            "address": {
+                "street": "Atatürk Cd. 1",
+                "city": "İstanbul",
+                "zipcode": 34000
+            },
+            "keys": {
+                "assertion_method": {
+                    "key_1": "0x8f2b1c0de4a7d1e7b9e6a1c2f3d4e5f60718293a4b5c6d7e8f90a1b2c3d4e5f6"
+                }
+            }
+        }"#,
+    )?;
+    let mutation: SdtClaim =
+        serde_json::from_str(r#"{"address": {"city": "Ankara", "zipcode": 6000}}"#)?;
+    let mutation2: SdtClaim =
+        serde_json::from_str(r#"{"personal": {"surname": "Çağlın Yılmaz"}}"#)?;
+    Ok(Sdt::new("did:p2p:123456", to_node(&inception, "0"))
+        .mutate(to_node(&mutation, "1"))
+        .mutate(to_node(&mutation2, "2"))
+        .build())
+}
+
+fn sizes(sdt: &Sdt) -> Result<(usize, usize, usize), SdtError> {
+    let json = serde_json::to_string_pretty(sdt)?.len();
+    let base45 = compact::encode(sdt, SdtArmor::Base45)?.len();
+    let base64url = compact::encode(sdt, SdtArmor::Base64Url)?.len();
+    Ok((json, base45, base64url))
+}
+
+#[test]
+fn selected_presentation_size_test() -> Result<(), SdtError> {
+    let sdt = create_sdt()?;
+    let selected = sdt.select("{\n personal {\n age_over_18\n }\n}")?;
+    let (json, base45, base64url) = sizes(&selected)?;
+    assert!(base45 <= 620, "base45 presentation grew to {base45} chars");
+    assert!(
+        base64url <= 550,
+        "base64url presentation grew to {base64url} chars"
+    );
+    assert!(
+        base64url * 2 < json,
+        "{base64url} chars is not half of {json}"
+    );
+    Ok(())
+}
+
+#[test]
+fn single_item_presentation_size_test() -> Result<(), SdtError> {
+    let sdt = Sdt::new("did:p2p:123456", create_sdt()?.current_node());
+    let selected = sdt.select("{\n personal {\n age_over_18\n }\n}")?;
+    let (_, base45, _) = sizes(&selected)?;
+    assert!(base45 <= 500, "base45 presentation grew to {base45} chars");
+    Ok(())
+}
+
+#[test]
+fn full_document_size_test() -> Result<(), SdtError> {
+    let (json, base45, base64url) = sizes(&create_sdt()?)?;
+    assert!(base45 <= 820, "base45 document grew to {base45} chars");
+    assert!(
+        base64url <= 730,
+        "base64url document grew to {base64url} chars"
+    );
+    assert!(base45 * 2 < json, "{base45} chars is not half of {json}");
+    Ok(())
+}
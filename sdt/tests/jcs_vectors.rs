@@ -0,0 +1,93 @@
+//! Test vectors for the JCS + HEX + SHA256 suite (version 2).
+//!
+//! `vectors/jcs_sha256.json` is meant to be shared with the verifiers in other
+//! languages, every implementation should reproduce the same canonical strings and proofs.
+
+use sdt::{
+    error::SdtError,
+    jcs::to_jcs_string,
+    node::SdtNode,
+    proof::SdtHashSuite,
+    value::{SdtValue, SdtValueKind},
+    Sdt,
+};
+use serde::Deserialize;
+
+const VECTORS: &str = include_str!("vectors/jcs_sha256.json");
+
+#[derive(Deserialize)]
+struct Vectors {
+    version: u64,
+    values: Vec<ValueVector>,
+    nodes: Vec<NodeVector>,
+    tries: Vec<TrieVector>,
+}
+
+#[derive(Deserialize)]
+struct ValueVector {
+    description: String,
+    salt: String,
+    value: SdtValueKind,
+    canonical: String,
+    proof: String,
+}
+
+#[derive(Deserialize)]
+struct NodeVector {
+    description: String,
+    node: SdtNode,
+    proof: String,
+}
+
+#[derive(Deserialize)]
+struct TrieVector {
+    description: String,
+    sdt: Sdt,
+    proof: String,
+}
+
+fn vectors() -> Vectors {
+    serde_json::from_str(VECTORS).expect("test vectors should be valid")
+}
+
+#[test]
+fn value_vectors_test() -> Result<(), SdtError> {
+    let vectors = vectors();
+    assert_eq!(vectors.version, SdtHashSuite::JcsSha256.version());
+    for v in vectors.values {
+        let value = SdtValue {
+            salt: v.salt,
+            value: v.value,
+        };
+        assert_eq!(to_jcs_string(&value)?, v.canonical, "{}", v.description);
+        assert_eq!(
+            value.gen_proof_with(SdtHashSuite::JcsSha256)?,
+            v.proof,
+            "{}",
+            v.description
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn node_vectors_test() -> Result<(), SdtError> {
+    for v in vectors().nodes {
+        assert_eq!(
+            v.node.gen_proof_with(SdtHashSuite::JcsSha256)?,
+            v.proof,
+            "{}",
+            v.description
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn trie_vectors_test() -> Result<(), SdtError> {
+    for v in vectors().tries {
+        assert_eq!(v.sdt.suite()?, SdtHashSuite::JcsSha256, "{}", v.description);
+        assert!(v.sdt.verify(&v.proof)?, "{}", v.description);
+    }
+    Ok(())
+}